@@ -0,0 +1,361 @@
+use crate::AppState;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub mod thruster;
+
+/// Plays the `Animation` of every sprite sheet entity.
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnimationFinished>()
+            .add_startup_system(thruster::thruster_setup_system)
+            .add_system(animation_system.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// Named clips of a sheet, shared by every entity playing them.
+pub type AnimationClips = Arc<HashMap<String, AnimationClip>>;
+
+/// Frames `first..=last` of an atlas and how to play them.
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimationClip {
+    pub first: usize,
+    pub last: usize,
+    /// seconds each frame is shown
+    pub frame_time: f32,
+    /// seconds of each frame from `first` on, `frame_time` for the frames it lacks
+    #[serde(default)]
+    pub frame_times: Vec<f32>,
+    #[serde(default)]
+    pub mode: LoopMode,
+    /// what happens once a `LoopMode::Once` clip shows its last frame
+    #[serde(default)]
+    pub on_finish: OnFinish,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    #[default]
+    Once,
+    Loop,
+    /// back and forth, first and last frames are not shown twice
+    PingPong,
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OnFinish {
+    /// stay on the last frame, only send `AnimationFinished`
+    #[default]
+    Event,
+    Despawn,
+    /// play the named clip of the same sheet
+    Chain(String),
+}
+
+/// Sent when a clip ends, whatever its `OnFinish`.
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub clip: String,
+}
+
+impl AnimationClip {
+    fn len(&self) -> usize {
+        self.last.saturating_sub(self.first) + 1
+    }
+
+    fn frame_time(&self, frame: usize) -> f32 {
+        // keep a broken clip from spinning the update loop
+        self.frame_times
+            .get(frame)
+            .copied()
+            .unwrap_or(self.frame_time)
+            .max(0.001)
+    }
+}
+
+/// Drives the `TextureAtlasSprite` index of its entity through a clip.
+#[derive(Component)]
+pub struct Animation {
+    clips: AnimationClips,
+    clip: String,
+    /// frame within the clip, from 0
+    frame: usize,
+    elapsed: f32,
+    backwards: bool,
+    finished: bool,
+}
+
+impl Animation {
+    /// Panics if `clips` has no `clip`.
+    pub fn new(clips: &AnimationClips, clip: &str) -> Self {
+        assert!(clips.contains_key(clip), "no animation clip `{clip}`");
+        Self {
+            clips: clips.clone(),
+            clip: clip.into(),
+            frame: 0,
+            elapsed: 0.,
+            backwards: false,
+            finished: false,
+        }
+    }
+
+    /// Restart on `clip`. Returns false for an unknown clip, the current one is kept.
+    pub fn play(&mut self, clip: &str) -> bool {
+        if !self.clips.contains_key(clip) {
+            warn!("No animation clip `{clip}`, keeping `{}`", self.clip);
+            return false;
+        }
+        self.clip = clip.into();
+        self.frame = 0;
+        self.elapsed = 0.;
+        self.backwards = false;
+        self.finished = false;
+        true
+    }
+
+    /// Atlas index of the current frame.
+    pub fn index(&self) -> usize {
+        self.clips[&self.clip].first + self.frame
+    }
+
+    /// Play `delta` more seconds. `finished` gets the name and `OnFinish` of every
+    /// clip that ends meanwhile; chains are followed here, despawning is left to it.
+    fn tick(&mut self, delta: f32, mut finished: impl FnMut(&str, &OnFinish)) {
+        if self.finished {
+            return;
+        }
+        self.elapsed += delta;
+        loop {
+            let clips = self.clips.clone();
+            let clip = &clips[&self.clip];
+            let frame_time = clip.frame_time(self.frame);
+            if self.elapsed < frame_time {
+                break;
+            }
+            self.elapsed -= frame_time;
+            if self.advance(clip) {
+                continue;
+            }
+
+            finished(&self.clip, &clip.on_finish);
+            self.finished = match &clip.on_finish {
+                OnFinish::Event | OnFinish::Despawn => true,
+                OnFinish::Chain(next) => !self.play(next),
+            };
+            if self.finished {
+                break;
+            }
+        }
+    }
+
+    /// Move to the next frame. Returns false when a `Once` clip is over.
+    fn advance(&mut self, clip: &AnimationClip) -> bool {
+        let len = clip.len();
+        match clip.mode {
+            LoopMode::Once if self.frame + 1 < len => self.frame += 1,
+            LoopMode::Once => return false,
+            LoopMode::Loop => self.frame = (self.frame + 1) % len,
+            LoopMode::PingPong if len == 1 => {}
+            LoopMode::PingPong => {
+                if self.backwards && self.frame == 0 || !self.backwards && self.frame + 1 == len {
+                    self.backwards = !self.backwards;
+                }
+                if self.backwards {
+                    self.frame -= 1;
+                } else {
+                    self.frame += 1;
+                }
+            }
+        }
+        true
+    }
+}
+
+fn animation_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut finished_events: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut Animation, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut animation, mut sprite) in query.iter_mut() {
+        if animation.finished {
+            continue;
+        }
+        animation.tick(time.delta_seconds(), |clip, on_finish| {
+            finished_events.send(AnimationFinished {
+                entity,
+                clip: clip.into(),
+            });
+            if *on_finish == OnFinish::Despawn {
+                commands.entity(entity).despawn_recursive();
+            }
+        });
+        sprite.index = animation.index();
+    }
+}
+
+/// Image of `frames` frames of `frame_size` side by side. `paint` gives the color
+/// of a pixel from the frame number and the pixel's offset to the frame center,
+/// y pointing up.
+pub fn strip(frame_size: UVec2, frames: u32, paint: impl Fn(u32, Vec2) -> [u8; 4]) -> Image {
+    let (width, height) = (frame_size.x * frames, frame_size.y);
+    let half = frame_size.as_vec2() / 2.;
+    let mut data = vec![0; (width * height * 4) as usize];
+    for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        let offset = Vec2::new(
+            (x % frame_size.x) as f32 + 0.5 - half.x,
+            half.y - y as f32 - 0.5,
+        );
+        pixel.copy_from_slice(&paint(x / frame_size.x, offset));
+    }
+    Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(first: usize, last: usize, mode: LoopMode, on_finish: OnFinish) -> AnimationClip {
+        AnimationClip {
+            first,
+            last,
+            frame_time: 1.,
+            frame_times: Vec::new(),
+            mode,
+            on_finish,
+        }
+    }
+
+    fn clips(clips: Vec<(&str, AnimationClip)>) -> AnimationClips {
+        Arc::new(
+            clips
+                .into_iter()
+                .map(|(name, clip)| (name.to_string(), clip))
+                .collect(),
+        )
+    }
+
+    /// Atlas indices shown over `steps` frame times, the first one included.
+    fn frames(animation: &mut Animation, steps: usize) -> Vec<usize> {
+        let mut shown = vec![animation.index()];
+        for _ in 0..steps {
+            animation.tick(1., |_, _| {});
+            shown.push(animation.index());
+        }
+        shown
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let clips = clips(vec![("a", clip(2, 4, LoopMode::Once, OnFinish::Event))]);
+        let mut animation = Animation::new(&clips, "a");
+        let mut ends = Vec::new();
+        for _ in 0..5 {
+            animation.tick(1., |clip, on_finish| {
+                ends.push((clip.to_string(), on_finish.clone()))
+            });
+        }
+        assert_eq!(animation.index(), 4);
+        // the last frame is shown for its time before the clip ends, once
+        assert_eq!(ends, vec![("a".to_string(), OnFinish::Event)]);
+    }
+
+    #[test]
+    fn once_frame_sequence() {
+        let clips = clips(vec![("a", clip(2, 4, LoopMode::Once, OnFinish::Event))]);
+        let mut animation = Animation::new(&clips, "a");
+        assert_eq!(frames(&mut animation, 4), vec![2, 3, 4, 4, 4]);
+    }
+
+    #[test]
+    fn loop_frame_sequence() {
+        let clips = clips(vec![("a", clip(0, 2, LoopMode::Loop, OnFinish::Event))]);
+        let mut animation = Animation::new(&clips, "a");
+        assert_eq!(frames(&mut animation, 6), vec![0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn ping_pong_frame_sequence() {
+        let clips = clips(vec![("a", clip(1, 3, LoopMode::PingPong, OnFinish::Event))]);
+        let mut animation = Animation::new(&clips, "a");
+        // the ends are not shown twice in a row
+        assert_eq!(frames(&mut animation, 8), vec![1, 2, 3, 2, 1, 2, 3, 2, 1]);
+    }
+
+    #[test]
+    fn ping_pong_single_frame() {
+        let clips = clips(vec![("a", clip(5, 5, LoopMode::PingPong, OnFinish::Event))]);
+        let mut animation = Animation::new(&clips, "a");
+        assert_eq!(frames(&mut animation, 3), vec![5, 5, 5, 5]);
+    }
+
+    #[test]
+    fn frame_times_shorter_than_the_clip() {
+        let mut short = clip(0, 2, LoopMode::Once, OnFinish::Event);
+        short.frame_times = vec![0.5];
+        let clips = clips(vec![("a", short)]);
+        let mut animation = Animation::new(&clips, "a");
+        // the first frame has its own time
+        animation.tick(0.5, |_, _| {});
+        assert_eq!(animation.index(), 1);
+        // the others fall back to `frame_time`
+        animation.tick(0.9, |_, _| {});
+        assert_eq!(animation.index(), 1);
+        animation.tick(0.1, |_, _| {});
+        assert_eq!(animation.index(), 2);
+    }
+
+    #[test]
+    fn chain_plays_the_next_clip() {
+        let clips = clips(vec![
+            ("a", clip(0, 1, LoopMode::Once, OnFinish::Chain("b".into()))),
+            ("b", clip(4, 5, LoopMode::Loop, OnFinish::Event)),
+        ]);
+        let mut animation = Animation::new(&clips, "a");
+        let mut ends = Vec::new();
+        // a shows 0 and 1, then b starts right away
+        for _ in 0..3 {
+            animation.tick(1., |clip, _| ends.push(clip.to_string()));
+        }
+        assert_eq!(ends, vec!["a"]);
+        assert_eq!(animation.index(), 5);
+    }
+
+    #[test]
+    fn despawn_ends_the_clip() {
+        let clips = clips(vec![("a", clip(0, 0, LoopMode::Once, OnFinish::Despawn))]);
+        let mut animation = Animation::new(&clips, "a");
+        let mut ends = Vec::new();
+        for _ in 0..3 {
+            animation.tick(1., |_, on_finish| ends.push(on_finish.clone()));
+        }
+        assert_eq!(ends, vec![OnFinish::Despawn]);
+    }
+
+    #[test]
+    fn play_unknown_clip_keeps_the_current_one() {
+        let clips = clips(vec![("a", clip(0, 3, LoopMode::Loop, OnFinish::Event))]);
+        let mut animation = Animation::new(&clips, "a");
+        animation.tick(2., |_, _| {});
+        assert!(!animation.play("missing"));
+        assert_eq!(animation.clip, "a");
+        assert_eq!(animation.index(), 2);
+        assert!(animation.play("a"));
+        assert_eq!(animation.index(), 0);
+    }
+}
@@ -0,0 +1,93 @@
+use super::{strip, Animation, AnimationClip, LoopMode, OnFinish};
+use crate::GameAtlas;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::sync::Arc;
+
+const FLAME_SIZE: (u32, u32) = (16, 32);
+/// length of the flame on each frame, as a fraction of the frame height
+const FLAME_LENGTHS: [f32; 4] = [0.7, 1., 0.8, 0.95];
+const FLAME_HALF_WIDTH: f32 = 7.;
+
+/// Generated flame sheet, played by the player's thruster and the enemies' idle glow.
+#[derive(Resource)]
+pub struct ThrusterTexture(pub GameAtlas);
+
+pub fn thruster_setup_system(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let frame_size = UVec2::new(FLAME_SIZE.0, FLAME_SIZE.1);
+    let frames = FLAME_LENGTHS.len() as u32;
+    // the flame comes out of the top edge and points down
+    let image = strip(frame_size, frames, |frame, offset| {
+        let length = FLAME_LENGTHS[frame as usize] * frame_size.y as f32;
+        let t = (frame_size.y as f32 / 2. - offset.y) / length;
+        let half_width = FLAME_HALF_WIDTH * (1. - t).max(0.).sqrt();
+        if t >= 1. || offset.x.abs() >= half_width {
+            return [0; 4];
+        }
+        let edge = offset.x.abs() / half_width;
+        let green = 250. - 180. * t;
+        let blue = (220. - 400. * t).max(0.);
+        let alpha = 255. * (1. - t) * (1. - 0.6 * edge);
+        [255, green as u8, blue as u8, alpha as u8]
+    });
+    let atlas = TextureAtlas::from_grid(
+        images.add(image),
+        frame_size.as_vec2(),
+        frames as usize,
+        1,
+        None,
+        None,
+    );
+
+    let clip = |frame_time, mode| AnimationClip {
+        first: 0,
+        last: frames as usize - 1,
+        frame_time,
+        frame_times: Vec::new(),
+        mode,
+        on_finish: OnFinish::Event,
+    };
+    let animations = HashMap::from_iter([
+        ("thrust".to_string(), clip(0.05, LoopMode::Loop)),
+        ("idle".to_string(), clip(0.15, LoopMode::PingPong)),
+    ]);
+    commands.insert_resource(ThrusterTexture(GameAtlas {
+        atlas: texture_atlases.add(atlas),
+        animations: Arc::new(animations),
+    }));
+}
+
+/// Spawn a flame playing `clip` under `parent`, coming out of `nozzle` in the
+/// parent's space. A flipped flame points up.
+pub fn spawn_thruster(
+    parent: &mut ChildBuilder,
+    thruster: &ThrusterTexture,
+    clip: &str,
+    nozzle: Vec3,
+    scale: f32,
+    flip_y: bool,
+) {
+    let half_length = FLAME_SIZE.1 as f32 / 2. * scale;
+    let offset = if flip_y { half_length } else { -half_length };
+    let transform =
+        Transform::from_translation(nozzle + Vec3::Y * offset).with_scale(Vec3::splat(scale));
+    let animation = Animation::new(&thruster.0.animations, clip);
+    parent.spawn((
+        SpriteSheetBundle {
+            texture_atlas: thruster.0.atlas.clone(),
+            sprite: TextureAtlasSprite {
+                index: animation.index(),
+                flip_y,
+                ..default()
+            },
+            transform,
+            ..default()
+        },
+        animation,
+        Name::new("Thruster"),
+    ));
+}
@@ -0,0 +1,254 @@
+use crate::wave::{Wave, WaveStarted};
+use crate::{AppState, BACKGROUND_COLOR, PLAYFIELD_SIZE};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use rand::{thread_rng, Rng};
+
+/// Star layers from the farthest: stars, star size in pixels, brightness and
+/// scroll speed in world units per second.
+const STAR_LAYERS: [(usize, usize, f32, f32); 3] =
+    [(140, 1, 0.35, 12.), (60, 2, 0.6, 28.), (18, 2, 1., 60.)];
+const IMAGE_SCROLL_SPEED: f32 = 6.;
+/// the camera sees down to z -0.1, lasers sit at 0
+const BACKGROUND_Z: f32 = -0.09;
+/// scroll speed factor added when a new wave starts
+const WARP_BOOST: f32 = 6.;
+/// fraction of the boost lost per second
+const WARP_DECAY: f32 = 1.5;
+/// scroll speed factor during boss waves
+const BOSS_SCROLL: f32 = 0.4;
+/// fraction of the way to the wave's colors covered per second
+const FADE_RATE: f32 = 1.5;
+
+/// Scrolling background: an optional image from the wave data, under layers of
+/// generated stars moving at different speeds.
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundScroll>()
+            .add_startup_system(background_setup_system)
+            .add_system(background_spawn_system.in_schedule(OnEnter(AppState::InGame)))
+            .add_systems(
+                (
+                    background_speed_system,
+                    background_scroll_system,
+                    background_wave_system,
+                )
+                    .in_set(OnUpdate(AppState::InGame)),
+            )
+            .add_system(background_cleanup_system.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+/// Scroll speed of the background as a factor of each layer's own speed.
+#[derive(Resource)]
+pub struct BackgroundScroll {
+    pub speed: f32,
+    /// extra speed left from the last wave change
+    boost: f32,
+}
+
+impl Default for BackgroundScroll {
+    fn default() -> Self {
+        Self {
+            speed: 1.,
+            boost: 0.,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct StarTextures(Vec<Handle<Image>>);
+
+/// One of the two tiles of a layer, they take turns at the top.
+#[derive(Component)]
+struct Scrolling {
+    speed: f32,
+}
+
+#[derive(Component, Clone)]
+struct StarLayer;
+
+#[derive(Component, Clone)]
+struct BackgroundImage;
+
+fn background_setup_system(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let textures = STAR_LAYERS
+        .iter()
+        .map(|&(stars, size, brightness, _)| images.add(star_layer(stars, size, brightness)))
+        .collect();
+    commands.insert_resource(StarTextures(textures));
+}
+
+/// Transparent playfield-sized image with `stars` squares of `size` pixels.
+fn star_layer(stars: usize, size: usize, brightness: f32) -> Image {
+    let (width, height) = (PLAYFIELD_SIZE.0 as usize, PLAYFIELD_SIZE.1 as usize);
+    let mut data = vec![0; width * height * 4];
+    let mut rng = thread_rng();
+    for _ in 0..stars {
+        let (x, y) = (
+            rng.gen_range(0..width - size),
+            rng.gen_range(0..height - size),
+        );
+        let value = (255. * brightness * rng.gen_range(0.6..=1.)) as u8;
+        for dy in 0..size {
+            let start = ((y + dy) * width + x) * 4;
+            for pixel in data[start..start + size * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&[value, value, value, 255]);
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn background_spawn_system(
+    mut commands: Commands,
+    star_textures: Res<StarTextures>,
+    mut scroll: ResMut<BackgroundScroll>,
+) {
+    // warp in at the start of a run
+    scroll.boost = WARP_BOOST;
+    // the wave system shows the image once it knows which one
+    spawn_tiles(
+        &mut commands,
+        default(),
+        IMAGE_SCROLL_SPEED,
+        BACKGROUND_Z,
+        Visibility::Hidden,
+        BackgroundImage,
+    );
+    for (i, (texture, layer)) in star_textures.0.iter().zip(STAR_LAYERS).enumerate() {
+        let z = BACKGROUND_Z + 0.01 * (i + 1) as f32;
+        spawn_tiles(
+            &mut commands,
+            texture.clone(),
+            layer.3,
+            z,
+            Visibility::Inherited,
+            StarLayer,
+        );
+    }
+}
+
+/// The two tiles of a layer, one on the playfield and one above it.
+fn spawn_tiles(
+    commands: &mut Commands,
+    texture: Handle<Image>,
+    speed: f32,
+    z: f32,
+    visibility: Visibility,
+    marker: impl Component + Clone,
+) {
+    let size = Vec2::new(PLAYFIELD_SIZE.0, PLAYFIELD_SIZE.1);
+    for tile in 0..2 {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(size),
+                    ..default()
+                },
+                texture: texture.clone(),
+                transform: Transform::from_xyz(0., tile as f32 * size.y, z),
+                visibility,
+                ..default()
+            },
+            Scrolling { speed },
+            marker.clone(),
+            Name::new("Background"),
+        ));
+    }
+}
+
+/// Warp when a wave starts, then settle on the wave's cruising speed.
+fn background_speed_system(
+    time: Res<Time>,
+    wave: Res<Wave>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut scroll: ResMut<BackgroundScroll>,
+) {
+    if !wave_started.is_empty() {
+        wave_started.clear();
+        scroll.boost = WARP_BOOST;
+    }
+    scroll.boost *= (-WARP_DECAY * time.delta_seconds()).exp();
+    let cruise = if wave.is_boss() { BOSS_SCROLL } else { 1. };
+    scroll.speed = cruise + scroll.boost;
+}
+
+fn background_scroll_system(
+    time: Res<Time>,
+    scroll: Res<BackgroundScroll>,
+    mut query: Query<(&mut Transform, &Scrolling)>,
+) {
+    let height = PLAYFIELD_SIZE.1;
+    for (mut transform, scrolling) in query.iter_mut() {
+        transform.translation.y -= scrolling.speed * scroll.speed * time.delta_seconds();
+        // a tile that leaves the bottom goes back on top of the other one
+        if transform.translation.y <= -height {
+            transform.translation.y += 2. * height;
+        }
+    }
+}
+
+/// Fade to the colors of the current wave and show its image.
+fn background_wave_system(
+    time: Res<Time>,
+    wave: Res<Wave>,
+    asset_server: Res<AssetServer>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut image: Local<Option<Handle<Image>>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut star_query: Query<&mut Sprite, (With<StarLayer>, Without<BackgroundImage>)>,
+    mut image_query: Query<
+        (&mut Handle<Image>, &mut Sprite, &mut Visibility),
+        (With<BackgroundImage>, Without<StarLayer>),
+    >,
+) {
+    let background = &wave.background;
+    let fade = (FADE_RATE * time.delta_seconds()).min(1.);
+    clear_color.0 = mix(clear_color.0, background.color, fade);
+    for mut sprite in star_query.iter_mut() {
+        sprite.color = mix(sprite.color, background.stars, fade);
+    }
+
+    if !wave_started.is_empty() {
+        wave_started.clear();
+        *image = background.image.map(|path| asset_server.load(path));
+    }
+    for (mut texture, mut sprite, mut visibility) in image_query.iter_mut() {
+        match &*image {
+            Some(image) if *texture != *image => {
+                // fade the new image in
+                *texture = image.clone();
+                *visibility = Visibility::Inherited;
+                sprite.color.set_a(0.);
+            }
+            Some(_) => {
+                let alpha = (sprite.color.a() + fade).min(1.);
+                sprite.color.set_a(alpha);
+            }
+            None if *visibility != Visibility::Hidden => *visibility = Visibility::Hidden,
+            None => {}
+        }
+    }
+}
+
+fn background_cleanup_system(mut clear_color: ResMut<ClearColor>) {
+    clear_color.0 = BACKGROUND_COLOR;
+}
+
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let from = Vec4::from(from.as_rgba_f32());
+    let to = Vec4::from(to.as_rgba_f32());
+    Color::from(from.lerp(to, t))
+}
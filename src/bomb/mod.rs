@@ -0,0 +1,91 @@
+use crate::components::{Enemy, ExplosionToSpawn, FromEnemy, Health, Laser, Player, SpriteSize};
+use crate::{AppState, EnemyHit, PlayerState, WinSize, BOMB_DAMAGE, BOMB_INVULNERABILITY};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+const BOMB_KEY: KeyCode = KeyCode::B;
+/// seconds the screen flash takes to fade out
+const BOMB_FLASH_TIME: f32 = 0.4;
+
+pub struct BombPlugin;
+
+impl Plugin for BombPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((player_bomb_system, bomb_flash_system).in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+#[derive(Component)]
+struct BombFlash(Timer);
+
+fn player_bomb_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    win_size: Res<WinSize>,
+    mut player_state: ResMut<PlayerState>,
+    mut enemy_hits: EventWriter<EnemyHit>,
+    player_query: Query<(), With<Player>>,
+    laser_query: Query<Entity, (With<Laser>, With<FromEnemy>)>,
+    enemy_query: Query<(Entity, &GlobalTransform, &SpriteSize, &Health), With<Enemy>>,
+) {
+    if !keyboard_input.just_pressed(BOMB_KEY) || player_state.bombs == 0 || player_query.is_empty()
+    {
+        return;
+    }
+    player_state.bombs -= 1;
+    player_state.invulnerable_until = time.elapsed_seconds_f64() + BOMB_INVULNERABILITY;
+
+    for laser_entity in laser_query.iter() {
+        commands.entity(laser_entity).despawn();
+    }
+    let half_screen = Vec2::new(win_size.width, win_size.height) / 2.;
+    for (enemy, transform, sprite_size, health) in enemy_query.iter() {
+        let transform = transform.compute_transform();
+        let size = sprite_size.0 * transform.scale.xy();
+        // enemies still flying in are out of reach
+        let offset = transform.translation.xy().abs() - size / 2.;
+        if offset.x > half_screen.x || offset.y > half_screen.y {
+            continue;
+        }
+        enemy_hits.send(EnemyHit {
+            enemy,
+            damage: BOMB_DAMAGE,
+        });
+        // the damage system blows up the ones this kills
+        if health.hp > BOMB_DAMAGE {
+            commands.spawn(ExplosionToSpawn {
+                translation: transform.translation,
+                size,
+            });
+        }
+    }
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1., 1., 1., 0.8),
+                custom_size: Some(Vec2::new(win_size.width, win_size.height)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., 50.),
+            ..default()
+        },
+        BombFlash(Timer::from_seconds(BOMB_FLASH_TIME, TimerMode::Once)),
+        Name::new("BombFlash"),
+    ));
+}
+
+fn bomb_flash_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Sprite, &mut BombFlash)>,
+) {
+    for (entity, mut sprite, mut flash) in query.iter_mut() {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color.set_a(0.8 * flash.0.percent_left());
+        }
+    }
+}
@@ -0,0 +1,216 @@
+/// CPU-side collision mask of a bunker, one flag per texture pixel.
+/// Row 0 is the top row, as in the texture data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BunkerMask {
+    width: usize,
+    height: usize,
+    solid: Vec<bool>,
+}
+
+impl BunkerMask {
+    /// Classic bunker silhouette: a block with bevelled top corners and an arch
+    /// cut into the bottom middle.
+    pub fn classic(width: usize, height: usize) -> Self {
+        let bevel = width / 5;
+        let arch_half_width = width / 5;
+        let arch_height = height * 2 / 5;
+        let center = width as f32 / 2.;
+
+        let mut solid = vec![true; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let from_edge = x.min(width - 1 - x);
+                let in_bevel = y < bevel && from_edge + y < bevel;
+
+                let dx = (x as f32 + 0.5 - center) / arch_half_width as f32;
+                let dy = (height - y) as f32 / arch_height as f32;
+                let in_arch = dx * dx + dy * dy < 1.;
+
+                if in_bevel || in_arch {
+                    solid[y * width + x] = false;
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            solid,
+        }
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| self.solid[i])
+    }
+
+    /// First solid pixel met by a projectile covering columns `x0..=x1` and rows
+    /// `y0..=y1`. Upward projectiles hit the lowest solid row first, downward ones
+    /// the highest.
+    pub fn first_hit(
+        &self,
+        (x0, y0): (i32, i32),
+        (x1, y1): (i32, i32),
+        upward: bool,
+    ) -> Option<(i32, i32)> {
+        let x0 = x0.max(0);
+        let x1 = x1.min(self.width as i32 - 1);
+        let y0 = y0.max(0);
+        let y1 = y1.min(self.height as i32 - 1);
+        if x0 > x1 || y0 > y1 {
+            return None;
+        }
+
+        let rows: Box<dyn Iterator<Item = i32>> = if upward {
+            Box::new((y0..=y1).rev())
+        } else {
+            Box::new(y0..=y1)
+        };
+        for y in rows {
+            if let Some(x) = (x0..=x1).find(|x| self.is_solid(*x, y)) {
+                return Some((x, y));
+            }
+        }
+        None
+    }
+
+    /// Clear every pixel within `radius` of (`cx`, `cy`). Returns the number of
+    /// pixels removed.
+    pub fn carve(&mut self, cx: i32, cy: i32, radius: i32) -> usize {
+        let mut removed = 0;
+        for y in cy - radius..=cy + radius {
+            for x in cx - radius..=cx + radius {
+                let (dx, dy) = (x - cx, y - cy);
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+                if let Some(i) = self.index(x, y) {
+                    if self.solid[i] {
+                        self.solid[i] = false;
+                        removed += 1;
+                    }
+                }
+            }
+        }
+        removed
+    }
+
+    /// Write the mask into RGBA8 pixel data: solid pixels get `color`, the rest
+    /// become fully transparent.
+    pub fn write_rgba(&self, data: &mut [u8], color: [u8; 4]) {
+        for (pixel, solid) in data.chunks_exact_mut(4).zip(self.solid.iter()) {
+            pixel.copy_from_slice(if *solid { &color } else { &[0, 0, 0, 0] });
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            None
+        } else {
+            Some(y as usize * self.width + x as usize)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mask from rows of `#` (solid) and `.` (empty), top row first.
+    fn mask(rows: &[&str]) -> BunkerMask {
+        BunkerMask {
+            width: rows[0].len(),
+            height: rows.len(),
+            solid: rows
+                .iter()
+                .flat_map(|row| row.chars())
+                .map(|c| c == '#')
+                .collect(),
+        }
+    }
+
+    fn full(width: usize, height: usize) -> BunkerMask {
+        BunkerMask {
+            width,
+            height,
+            solid: vec![true; width * height],
+        }
+    }
+
+    #[test]
+    fn classic_silhouette() {
+        let mask = BunkerMask::classic(40, 30);
+        // bevelled top corners
+        assert!(!mask.is_solid(0, 0));
+        assert!(!mask.is_solid(39, 0));
+        // solid top middle and bottom corners
+        assert!(mask.is_solid(20, 0));
+        assert!(mask.is_solid(0, 29));
+        assert!(mask.is_solid(39, 29));
+        // arch in the bottom middle
+        assert!(!mask.is_solid(19, 29));
+        assert!(!mask.is_solid(20, 25));
+        assert!(mask.is_solid(20, 10));
+        // outside of the mask
+        assert!(!mask.is_solid(-1, 10));
+        assert!(!mask.is_solid(40, 10));
+    }
+
+    #[test]
+    fn first_hit_by_direction() {
+        let mask = mask(&["....", ".#..", "..#.", "...."]);
+        // upward shots meet the lowest solid row first
+        assert_eq!(mask.first_hit((0, 0), (3, 3), true), Some((2, 2)));
+        // downward shots the highest
+        assert_eq!(mask.first_hit((0, 0), (3, 3), false), Some((1, 1)));
+    }
+
+    #[test]
+    fn first_hit_miss() {
+        let mask = mask(&["....", ".#..", "..#.", "...."]);
+        assert_eq!(mask.first_hit((3, 0), (3, 3), true), None);
+        assert_eq!(mask.first_hit((0, 3), (3, 3), false), None);
+    }
+
+    #[test]
+    fn first_hit_clips_columns() {
+        let mask = mask(&["#..#", "....", "....", "...."]);
+        // partly outside, clipped to the mask
+        assert_eq!(mask.first_hit((-5, 0), (0, 3), false), Some((0, 0)));
+        assert_eq!(mask.first_hit((3, 0), (10, 3), false), Some((3, 0)));
+        // entirely outside
+        assert_eq!(mask.first_hit((-5, 0), (-1, 3), false), None);
+        assert_eq!(mask.first_hit((4, 0), (10, 3), true), None);
+    }
+
+    #[test]
+    fn carve_counts_removed_pixels() {
+        let mut mask = full(10, 10);
+        // a radius 1 disc is the center and its 4 neighbours
+        assert_eq!(mask.carve(5, 5, 1), 5);
+        assert!(!mask.is_solid(5, 5));
+        assert!(!mask.is_solid(5, 4));
+        assert!(mask.is_solid(4, 4));
+        // nothing left to remove there
+        assert_eq!(mask.carve(5, 5, 1), 0);
+    }
+
+    #[test]
+    fn carve_clips_at_edges() {
+        let mut mask = full(10, 10);
+        assert_eq!(mask.carve(0, 0, 1), 3);
+        assert_eq!(mask.carve(9, 9, 1), 3);
+        assert_eq!(mask.carve(-5, -5, 1), 0);
+    }
+
+    #[test]
+    fn write_rgba_alpha() {
+        let mut mask = full(2, 2);
+        mask.carve(0, 0, 0);
+        let mut data = vec![255; 2 * 2 * 4];
+        mask.write_rgba(&mut data, [10, 20, 30, 255]);
+        assert_eq!(&data[0..4], &[0, 0, 0, 0]);
+        for pixel in data[4..].chunks_exact(4) {
+            assert_eq!(pixel, &[10, 20, 30, 255]);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::bunker::mask::BunkerMask;
+use crate::collision::world_extent;
+use crate::components::{Hitbox, Laser, SpriteSize, Velocity};
+use crate::{AppState, WinSize, BUNKER_COUNT, BUNKER_PIXELS, BUNKER_SCALE};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use bevy::sprite::collide_aabb::collide;
+
+pub mod mask;
+
+const BUNKER_COLOR: [u8; 4] = [80, 220, 90, 255];
+/// crater radius in bunker pixels
+const CRATER_RADIUS: i32 = 3;
+/// distance of the bunker row above the bottom of the window
+const BUNKER_ROW_HEIGHT: f32 = 140.;
+
+pub struct BunkerPlugin;
+
+impl Plugin for BunkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(bunker_spawn_system.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(laser_hit_bunker_system.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+#[derive(Component)]
+pub struct Bunker {
+    pub mask: BunkerMask,
+}
+
+fn bunker_spawn_system(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    win_size: Res<WinSize>,
+) {
+    let (width, height) = BUNKER_PIXELS;
+    let spacing = win_size.width / BUNKER_COUNT as f32;
+    let y = -win_size.height / 2. + BUNKER_ROW_HEIGHT;
+
+    for i in 0..BUNKER_COUNT {
+        let mask = BunkerMask::classic(width, height);
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+        );
+        image.sampler_descriptor = ImageSampler::nearest();
+        mask.write_rgba(&mut image.data, BUNKER_COLOR);
+
+        let x = -win_size.width / 2. + spacing * (i as f32 + 0.5);
+        commands.spawn((
+            SpriteBundle {
+                texture: images.add(image),
+                transform: Transform {
+                    translation: Vec3::new(x, y, 5.),
+                    scale: Vec3::new(BUNKER_SCALE, BUNKER_SCALE, 1.),
+                    ..default()
+                },
+                ..default()
+            },
+            Bunker { mask },
+            SpriteSize::from((width as f32, height as f32)),
+            Name::new("Bunker"),
+        ));
+    }
+}
+
+/// Lasers from both sides erode the bunkers. The AABB is only a broad test, the
+/// hit is decided against the remaining pixels of the mask.
+fn laser_hit_bunker_system(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    laser_query: Query<(Entity, &Transform, &Hitbox, &Velocity), With<Laser>>,
+    mut bunker_query: Query<(&Transform, &SpriteSize, &Handle<Image>, &mut Bunker)>,
+) {
+    for (laser_entity, laser_transform, laser_hitbox, laser_velocity) in laser_query.iter() {
+        let laser_extent = world_extent(laser_hitbox.0, laser_transform);
+        for (bunker_transform, bunker_size, image_handle, mut bunker) in bunker_query.iter_mut() {
+            let bunker_scale = bunker_transform.scale.xy();
+            let collision = collide(
+                laser_transform.translation,
+                laser_extent,
+                bunker_transform.translation,
+                bunker_size.0 * bunker_scale,
+            );
+            if collision.is_none() {
+                continue;
+            }
+
+            // laser rectangle in mask pixel coordinates (row 0 at the top)
+            let to_mask = |world: Vec2| {
+                let local = (world - bunker_transform.translation.xy()) / bunker_scale;
+                (
+                    (local.x + bunker_size.0.x / 2.).floor() as i32,
+                    (bunker_size.0.y / 2. - local.y).floor() as i32,
+                )
+            };
+            let center = laser_transform.translation.xy();
+            let (x0, y1) = to_mask(center - laser_extent / 2.);
+            let (x1, y0) = to_mask(center + laser_extent / 2.);
+
+            let upward = laser_velocity.y > 0.;
+            let Some((hit_x, hit_y)) = bunker.mask.first_hit((x0, y0), (x1, y1), upward) else {
+                continue;
+            };
+
+            bunker.mask.carve(hit_x, hit_y, CRATER_RADIUS);
+            if let Some(image) = images.get_mut(image_handle) {
+                bunker.mask.write_rgba(&mut image.data, BUNKER_COLOR);
+            }
+            commands.entity(laser_entity).despawn();
+            break;
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::{Entity, Transform, Vec2, Vec3};
+use bevy::utils::HashMap;
+
+/// Distance along `direction` (a unit vector) at which a ray from `origin` enters
+/// the axis-aligned box of `size` centered on `center`, if it does.
+pub fn ray_box(origin: Vec2, direction: Vec2, center: Vec2, size: Vec2) -> Option<f32> {
+    let min = center - size / 2.;
+    let max = center + size / 2.;
+
+    let mut t_enter = 0_f32;
+    let mut t_exit = f32::INFINITY;
+    for axis in 0..2 {
+        let (o, d, lo, hi) = (origin[axis], direction[axis], min[axis], max[axis]);
+        if d.abs() < f32::EPSILON {
+            // parallel to this slab, the origin has to be inside it
+            if o < lo || o > hi {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = ((lo - o) / d, (hi - o) / d);
+        t_enter = t_enter.max(t0.min(t1));
+        t_exit = t_exit.min(t0.max(t1));
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+    Some(t_enter)
+}
+
+/// World size of the axis-aligned box around a sprite of `size` once its
+/// transform is applied, so turned projectiles collide the way they look.
+pub fn world_extent(size: Vec2, transform: &Transform) -> Vec2 {
+    let scaled = size * transform.scale.xy();
+    let axis = (transform.rotation * Vec3::X).xy();
+    let (cos, sin) = (axis.x.abs(), axis.y.abs());
+    Vec2::new(
+        cos * scaled.x + sin * scaled.y,
+        sin * scaled.x + cos * scaled.y,
+    )
+}
+
+/// Broadphase for box-vs-box tests: boxes are bucketed into square cells so a
+/// query only looks at the boxes sharing a cell with it, instead of all of them.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    boxes: Vec<(Entity, Vec2, Vec2)>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            boxes: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, center: Vec2, size: Vec2) {
+        let (min, max) = (center - size / 2., center + size / 2.);
+        let index = self.boxes.len();
+        self.boxes.push((entity, min, max));
+        for cell in self.cells_of(min, max) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    /// Entities whose box overlaps the box of `size` centered on `center`,
+    /// each one once, in insertion order.
+    pub fn query(&self, center: Vec2, size: Vec2) -> Vec<Entity> {
+        let (min, max) = (center - size / 2., center + size / 2.);
+        let mut hits: Vec<usize> = self
+            .cells_of(min, max)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|index| {
+                let (_, other_min, other_max) = self.boxes[*index];
+                min.x < other_max.x
+                    && other_min.x < max.x
+                    && min.y < other_max.y
+                    && other_min.y < max.y
+            })
+            .collect();
+        hits.sort_unstable();
+        hits.dedup();
+        hits.into_iter().map(|index| self.boxes[index].0).collect()
+    }
+
+    fn cells_of(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (i32, i32)> {
+        let (x0, y0) = self.cell(min);
+        let (x1, y1) = self.cell(max);
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+    }
+
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::Quat;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    const CENTER: Vec2 = Vec2::new(10., 0.);
+    const SIZE: Vec2 = Vec2::new(4., 4.);
+
+    #[test]
+    fn ray_box_hit() {
+        let distance = ray_box(Vec2::ZERO, Vec2::X, CENTER, SIZE);
+        assert_eq!(distance, Some(8.));
+    }
+
+    #[test]
+    fn ray_box_miss() {
+        // pointing away from the box
+        assert_eq!(ray_box(Vec2::ZERO, -Vec2::X, CENTER, SIZE), None);
+        // passing above it
+        let direction = Vec2::new(1., 1.).normalize();
+        assert_eq!(ray_box(Vec2::ZERO, direction, CENTER, SIZE), None);
+    }
+
+    #[test]
+    fn ray_box_origin_inside() {
+        assert_eq!(ray_box(CENTER, Vec2::Y, CENTER, SIZE), Some(0.));
+    }
+
+    #[test]
+    fn ray_box_parallel() {
+        // along the x slab, inside and outside of the y one
+        let distance = ray_box(Vec2::new(0., 1.), Vec2::X, CENTER, SIZE);
+        assert_eq!(distance, Some(8.));
+        assert_eq!(ray_box(Vec2::new(0., 3.), Vec2::X, CENTER, SIZE), None);
+    }
+
+    #[test]
+    fn world_extent_rotated() {
+        let transform = Transform::from_scale(Vec3::new(2., 2., 1.));
+        let size = Vec2::new(2., 8.);
+        assert_eq!(world_extent(size, &transform), Vec2::new(4., 16.));
+
+        // a quarter turn swaps width and height
+        let turned = transform.with_rotation(Quat::from_rotation_z(FRAC_PI_2));
+        let extent = world_extent(size, &turned);
+        assert!((extent - Vec2::new(16., 4.)).length() < 1e-4);
+
+        // at 45 degrees both sides take half of each
+        let diagonal = transform.with_rotation(Quat::from_rotation_z(FRAC_PI_4));
+        let extent = world_extent(size, &diagonal);
+        assert!((extent - Vec2::splat(20. / 2_f32.sqrt())).length() < 1e-4);
+    }
+
+    const CELL: f32 = 10.;
+
+    #[test]
+    fn grid_overlap_across_cells() {
+        let mut grid = SpatialGrid::new(CELL);
+        // right at the edge of cell 0, only its tip reaches into cell 1
+        grid.insert(Entity::from_raw(0), Vec2::new(8.5, 5.), SIZE);
+        assert_eq!(
+            grid.query(Vec2::new(12., 5.), SIZE),
+            vec![Entity::from_raw(0)]
+        );
+    }
+
+    #[test]
+    fn grid_large_box_once() {
+        let mut grid = SpatialGrid::new(CELL);
+        // spans 4 by 4 cells
+        grid.insert(
+            Entity::from_raw(0),
+            Vec2::new(20., 20.),
+            Vec2::new(35., 35.),
+        );
+        grid.insert(Entity::from_raw(1), Vec2::new(100., 100.), SIZE);
+        assert_eq!(
+            grid.query(Vec2::new(20., 20.), Vec2::new(30., 30.)),
+            vec![Entity::from_raw(0)]
+        );
+    }
+
+    #[test]
+    fn grid_near_miss_same_cell() {
+        let mut grid = SpatialGrid::new(CELL);
+        grid.insert(Entity::from_raw(0), Vec2::new(2., 2.), Vec2::new(2., 2.));
+        // same cell, 2 units apart
+        assert!(grid.query(Vec2::new(6., 2.), Vec2::new(2., 2.)).is_empty());
+    }
+
+    #[test]
+    fn grid_negative_coordinates() {
+        let mut grid = SpatialGrid::new(CELL);
+        // in cell -1 on both axes
+        grid.insert(Entity::from_raw(0), Vec2::new(-3., -3.), SIZE);
+        grid.insert(Entity::from_raw(1), Vec2::new(3., 3.), Vec2::new(2., 2.));
+        assert_eq!(
+            grid.query(Vec2::new(-6., -6.), SIZE),
+            vec![Entity::from_raw(0)]
+        );
+        // across the origin, from cell -1 into cell 0
+        assert_eq!(
+            grid.query(Vec2::new(0.5, 0.5), SIZE),
+            vec![Entity::from_raw(0), Entity::from_raw(1)]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use bevy::math::Vec3;
-use bevy::prelude::{Component, Timer, TimerMode, Vec2};
+use bevy::prelude::{Component, Entity, Timer, TimerMode, Vec2};
+use rand::{thread_rng, Rng};
+use std::time::Duration;
 
 #[derive(Component)]
 pub struct Velocity {
@@ -12,9 +14,39 @@ pub struct Movable {
     pub auto_despawn: bool,
 }
 
+/// Rotates the sprite to face its `Velocity`, the sprite pointing up at rest.
+#[derive(Component)]
+pub struct FaceVelocity;
+
+/// Turns the `Velocity` toward `target` by at most `turn_rate` radians per second,
+/// keeping its speed. Picks the nearest `Enemy` when it has no (living) target.
+#[derive(Component)]
+pub struct Homing {
+    pub target: Option<Entity>,
+    pub turn_rate: f32,
+}
+
+/// Despawns the entity when the timer runs out.
+#[derive(Component)]
+pub struct Lifetime(pub Timer);
+
+impl Lifetime {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
 #[derive(Component)]
 pub struct Laser;
 
+/// A projectile the player can shoot down.
+#[derive(Component)]
+pub struct Destructible;
+
+/// Damage dealt by a projectile, 1 when absent.
+#[derive(Component)]
+pub struct Damage(pub u32);
+
 #[derive(Component)]
 pub struct SpriteSize(pub Vec2);
 
@@ -24,6 +56,31 @@ impl From<(f32, f32)> for SpriteSize {
     }
 }
 
+/// Collision box in sprite pixels, the manifest's `hitbox` of the sprite.
+#[derive(Component)]
+pub struct Hitbox(pub Vec2);
+
+#[derive(Component)]
+pub struct Health {
+    pub hp: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { hp: max, max }
+    }
+    pub fn damage(&mut self, amount: u32) {
+        self.hp = self.hp.saturating_sub(amount);
+    }
+    pub fn is_dead(&self) -> bool {
+        self.hp == 0
+    }
+    pub fn fraction(&self) -> f32 {
+        self.hp as f32 / self.max as f32
+    }
+}
+
 #[derive(Component)]
 pub struct Player;
 
@@ -37,16 +94,40 @@ pub struct Enemy;
 pub struct FromEnemy;
 
 #[derive(Component)]
-pub struct Explosion;
+pub struct ExplosionToSpawn {
+    pub translation: Vec3,
+    /// world size of what blew up, scales the particle burst
+    pub size: Vec2,
+}
 
 #[derive(Component)]
-pub struct ExplosionToSpawn(pub Vec3);
+pub struct PowerUpToSpawn(pub Vec3);
+
+#[derive(Component)]
+pub struct FireCooldown(pub Timer);
+
+impl FireCooldown {
+    pub fn new(delay: Duration) -> Self {
+        Self(Timer::new(delay, TimerMode::Once))
+    }
+}
+
+/// Average seconds between volleys, for enemies that don't follow the wave's fire rate.
+#[derive(Component, Clone, Copy)]
+pub struct FireInterval(pub f32);
+
+impl FireInterval {
+    /// Randomized delay until the next volley, within 20% of the interval.
+    pub fn next_delay(&self) -> Duration {
+        Duration::from_secs_f32(self.0 * thread_rng().gen_range(0.8..1.2))
+    }
+}
 
 #[derive(Component)]
-pub struct ExplosionTimer(pub Timer);
+pub struct FloatingText(pub Timer);
 
-impl Default for ExplosionTimer {
+impl Default for FloatingText {
     fn default() -> Self {
-        Self(Timer::from_seconds(0.05, TimerMode::Repeating))
+        Self(Timer::from_seconds(1.2, TimerMode::Once))
     }
 }
@@ -0,0 +1,340 @@
+use crate::components::{
+    Enemy, ExplosionToSpawn, FireCooldown, FireInterval, Health, Hitbox, SpriteSize,
+};
+use crate::wave::Wave;
+use crate::weapon::{Aim, Projectile, Weapon};
+use crate::{GameTexture, WinSize, TIME_STEP};
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::PI;
+
+const BOSS_HULL_SCALE: f32 = 1.2;
+const BOSS_TURRET_SCALE: f32 = 0.3;
+const BOSS_CORE_SCALE: f32 = 0.35;
+const BOSS_TURRET_OFFSET: (f32, f32) = (62., -20.);
+const BOSS_CORE_OFFSET: (f32, f32) = (0., -30.);
+const BOSS_TURRET_HEALTH: u32 = 12;
+const BOSS_CORE_HEALTH: u32 = 40;
+const BOSS_ENTRY_SPEED: f32 = 40.;
+const BOSS_SWAY: (f32, f32) = (120., 0.6);
+/// core health fractions below which the boss enters its next phase
+const BOSS_PHASE_THRESHOLDS: [f32; 2] = [0.66, 0.33];
+const BOSS_CHAIN_EXPLOSIONS: u32 = 10;
+const BOSS_TURRET_POINTS: u32 = 100;
+/// includes the bonus for beating the boss
+const BOSS_CORE_POINTS: u32 = 1000;
+
+/// Root of a boss ship. Its parts are children of this entity.
+#[derive(Component)]
+pub struct Boss {
+    pub phase: usize,
+    pub rest_y: f32,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum BossPart {
+    Turret,
+    Core,
+}
+
+impl BossPart {
+    /// score for destroying this part
+    pub fn points(self) -> u32 {
+        match self {
+            BossPart::Turret => BOSS_TURRET_POINTS,
+            BossPart::Core => BOSS_CORE_POINTS,
+        }
+    }
+}
+
+/// Explosions going off one after another around `center`.
+#[derive(Component)]
+pub struct ChainExplosion {
+    pub center: Vec3,
+    pub radius: f32,
+    pub remaining: u32,
+    pub timer: Timer,
+}
+
+pub fn boss_spawn_system(
+    mut commands: Commands,
+    mut wave: ResMut<Wave>,
+    game_texture: Res<GameTexture>,
+    win_size: Res<WinSize>,
+    boss_query: Query<(), With<Boss>>,
+) {
+    if !wave.is_boss() || wave.boss_spawned || !boss_query.is_empty() {
+        return;
+    }
+    wave.boss_spawned = true;
+
+    let top = win_size.height / 2.;
+    let size = game_texture.enemy.size;
+    let hitbox = game_texture.enemy.hitbox;
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_xyz(0., top + size.y, 10.)),
+            Boss {
+                phase: 0,
+                rest_y: top - size.y * BOSS_HULL_SCALE,
+            },
+            Name::new("Boss"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    texture: game_texture.enemy.image.clone(),
+                    sprite: Sprite {
+                        color: Color::rgb(0.8, 0.6, 1.),
+                        ..default()
+                    },
+                    transform: Transform::from_scale(Vec3::new(
+                        BOSS_HULL_SCALE,
+                        BOSS_HULL_SCALE,
+                        1.,
+                    )),
+                    ..default()
+                },
+                Name::new("BossHull"),
+            ));
+
+            for side in [-1., 1.] {
+                parent.spawn((
+                    boss_part_sprite(
+                        &game_texture,
+                        Color::rgb(1., 0.8, 0.4),
+                        Vec3::new(side * BOSS_TURRET_OFFSET.0, BOSS_TURRET_OFFSET.1, 1.),
+                        BOSS_TURRET_SCALE,
+                    ),
+                    Enemy,
+                    BossPart::Turret,
+                    Health::new(BOSS_TURRET_HEALTH),
+                    SpriteSize(size),
+                    Hitbox(hitbox),
+                    phase_weapon(0),
+                    phase_fire_interval(0),
+                    FireCooldown::new(phase_fire_interval(0).next_delay()),
+                    Name::new("BossTurret"),
+                ));
+            }
+
+            parent.spawn((
+                boss_part_sprite(
+                    &game_texture,
+                    Color::rgb(1., 0.3, 0.3),
+                    Vec3::new(BOSS_CORE_OFFSET.0, BOSS_CORE_OFFSET.1, 2.),
+                    BOSS_CORE_SCALE,
+                ),
+                Enemy,
+                BossPart::Core,
+                Health::new(BOSS_CORE_HEALTH),
+                SpriteSize(size),
+                Hitbox(hitbox),
+                Name::new("BossCore"),
+            ));
+        });
+    debug!("Boss spawned for wave {}", wave.number);
+}
+
+fn boss_part_sprite(
+    game_texture: &GameTexture,
+    color: Color,
+    translation: Vec3,
+    scale: f32,
+) -> SpriteBundle {
+    SpriteBundle {
+        texture: game_texture.enemy.image.clone(),
+        sprite: Sprite { color, ..default() },
+        transform: Transform {
+            translation,
+            scale: Vec3::new(scale, scale, 1.),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+/// Turret weapon for each boss phase.
+fn phase_weapon(phase: usize) -> Weapon {
+    let weapon = match phase {
+        0 => Weapon::new(Projectile::EnemyLaser, Aim::Fixed(Vec2::NEG_Y), 1., 0.)
+            .with_spread(3, PI / 6.),
+        1 => Weapon::enemy_spiral(1., 6, PI / 12.),
+        _ => Weapon::new(
+            Projectile::EnemyLaser,
+            Aim::AtPlayer { lead: false },
+            1.2,
+            0.1,
+        )
+        .with_burst(5),
+    };
+    weapon.with_muzzles(vec![Vec2::new(0., -15.)])
+}
+
+/// Turret (and last phase core) fire rate for each boss phase.
+fn phase_fire_interval(phase: usize) -> FireInterval {
+    FireInterval([1.6, 1.0, 0.8][phase.min(2)])
+}
+
+pub fn boss_movement_system(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &Boss), Without<Enemy>>,
+) {
+    for (mut transform, boss) in query.iter_mut() {
+        if transform.translation.y > boss.rest_y {
+            transform.translation.y =
+                (transform.translation.y - BOSS_ENTRY_SPEED * TIME_STEP).max(boss.rest_y);
+        } else {
+            let (amplitude, frequency) = BOSS_SWAY;
+            transform.translation.x = amplitude * (time.elapsed_seconds() * frequency).sin();
+        }
+    }
+}
+
+/// Move the boss to its next phase when the core health drops under a threshold.
+pub fn boss_phase_system(
+    mut commands: Commands,
+    mut boss_query: Query<(&mut Boss, &Children)>,
+    core_query: Query<(&Health, &BossPart), Changed<Health>>,
+    mut turret_query: Query<(&BossPart, &mut Weapon, &mut FireInterval, &mut FireCooldown)>,
+) {
+    for (mut boss, children) in boss_query.iter_mut() {
+        let Some(core_health) = children
+            .iter()
+            .find_map(|child| match core_query.get(*child) {
+                Ok((health, BossPart::Core)) => Some(health),
+                _ => None,
+            })
+        else {
+            continue;
+        };
+
+        let phase = BOSS_PHASE_THRESHOLDS
+            .iter()
+            .filter(|threshold| core_health.fraction() < **threshold)
+            .count();
+        if phase <= boss.phase {
+            continue;
+        }
+        boss.phase = phase;
+        debug!("Boss phase {}", phase);
+
+        for child in children.iter() {
+            if let Ok((part, mut weapon, mut interval, mut cooldown)) = turret_query.get_mut(*child)
+            {
+                if *part == BossPart::Turret {
+                    *weapon = phase_weapon(phase);
+                    *interval = phase_fire_interval(phase);
+                    *cooldown = FireCooldown::new(interval.next_delay());
+                }
+            } else if core_query.contains(*child) && phase == BOSS_PHASE_THRESHOLDS.len() {
+                // last phase: the exposed core starts shooting back
+                commands.entity(*child).insert((
+                    Weapon::new(
+                        Projectile::EnemyLaser,
+                        Aim::AtPlayer { lead: true },
+                        1.2,
+                        0.,
+                    )
+                    .with_muzzles(vec![Vec2::new(0., -15.)]),
+                    phase_fire_interval(phase),
+                    FireCooldown::new(phase_fire_interval(phase).next_delay()),
+                ));
+            }
+        }
+    }
+}
+
+/// Despawn destroyed boss parts. Losing the core destroys the whole boss.
+pub fn boss_part_destroyed_system(
+    mut commands: Commands,
+    mut wave: ResMut<Wave>,
+    game_texture: Res<GameTexture>,
+    part_query: Query<(Entity, &BossPart, &Health, &Parent), Changed<Health>>,
+    boss_query: Query<&GlobalTransform, With<Boss>>,
+) {
+    for (entity, part, health, parent) in part_query.iter() {
+        if !health.is_dead() {
+            continue;
+        }
+        match part {
+            BossPart::Turret => commands.entity(entity).despawn_recursive(),
+            BossPart::Core => {
+                if let Ok(boss_transform) = boss_query.get(parent.get()) {
+                    commands.spawn(ChainExplosion {
+                        center: boss_transform.translation(),
+                        radius: game_texture.enemy.size.x * BOSS_HULL_SCALE / 2.,
+                        remaining: BOSS_CHAIN_EXPLOSIONS,
+                        timer: Timer::from_seconds(0.15, TimerMode::Repeating),
+                    });
+                }
+                commands.entity(parent.get()).despawn_recursive();
+                wave.complete();
+            }
+        }
+    }
+}
+
+pub fn chain_explosion_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ChainExplosion)>,
+) {
+    let mut rng = thread_rng();
+    for (entity, mut chain) in query.iter_mut() {
+        if !chain.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let offset =
+            Vec2::from_angle(rng.gen_range(0.0..2. * PI)) * rng.gen_range(0.0..chain.radius);
+        commands.spawn(ExplosionToSpawn {
+            translation: chain.center + offset.extend(0.),
+            size: Vec2::splat(chain.radius),
+        });
+
+        chain.remaining -= 1;
+        if chain.remaining == 0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::enemy_fire_system;
+    use std::time::Duration;
+
+    #[test]
+    fn turret_keeps_phase_interval_after_firing() {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        // late wave, its fire delays stay under the first phase's ones
+        world.insert_resource(Wave::new(20));
+        let mut schedule = Schedule::new();
+        schedule.add_system(enemy_fire_system);
+
+        for phase in 0..3 {
+            let interval = phase_fire_interval(phase);
+            let turret = world
+                .spawn((
+                    Enemy,
+                    BossPart::Turret,
+                    phase_weapon(phase),
+                    interval,
+                    FireCooldown::new(Duration::ZERO),
+                ))
+                .id();
+            schedule.run(&mut world);
+
+            assert!(world.get::<Weapon>(turret).unwrap().trigger);
+            let delay = world.get::<FireCooldown>(turret).unwrap().0.duration();
+            let delay = delay.as_secs_f32();
+            assert!(
+                (interval.0 * 0.8..=interval.0 * 1.2).contains(&delay),
+                "phase {phase}: {delay}s"
+            );
+            world.despawn(turret);
+        }
+    }
+}
@@ -1,39 +1,57 @@
-use crate::components::{Enemy, FromEnemy, Laser, Movable, SpriteSize, Velocity};
-use crate::enemy::formation::{Formation, FormationMaker};
-use crate::{
-    EnemyCount, GameTexture, WinSize, BASE_SPEED, ENEMY_LASER_SIZE, ENEMY_MAX, ENEMY_SIZE,
-    SPRITE_SCALE, TIME_STEP,
+use crate::animation::thruster::{spawn_thruster, ThrusterTexture};
+use crate::components::{
+    Enemy, FireCooldown, FireInterval, FromEnemy, Health, Hitbox, Laser, SpriteSize,
+};
+use crate::enemy::boss::{
+    boss_movement_system, boss_part_destroyed_system, boss_phase_system, boss_spawn_system,
+    chain_explosion_system,
 };
+use crate::enemy::formation::{Formation, FormationMaker};
+use crate::wave::Wave;
+use crate::weapon::Weapon;
+use crate::{AppState, EnemyCount, GameTexture, WinSize, ENEMY_MAX, SPRITE_SCALE, TIME_STEP};
 use bevy::prelude::*;
 use bevy::time::common_conditions::on_timer;
-use rand::{thread_rng, Rng};
 use std::f32::consts::PI;
 use std::time::Duration;
 
+pub mod boss;
 mod formation;
 
+const ENEMY_LASER_SPEED: f32 = 1.;
+
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(FormationMaker::default())
             //.add_startup_systems((enemy_spawn_system.in_base_set(StartupSet::PostStartup),))
-            .add_systems((
-                enemy_spawn_system.run_if(on_timer(Duration::from_secs(1))),
-                enemy_fire_system.run_if(enemy_fire_criteria),
-                enemy_movement_system,
-            ));
+            .add_systems(
+                (
+                    enemy_spawn_system.run_if(on_timer(Duration::from_secs(1))),
+                    enemy_fire_system,
+                    enemy_movement_system,
+                    boss_spawn_system,
+                    boss_movement_system,
+                    boss_phase_system,
+                    boss_part_destroyed_system,
+                    chain_explosion_system,
+                )
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
     }
 }
 
 fn enemy_spawn_system(
     mut commands: Commands,
     game_texture: Res<GameTexture>,
+    thruster: Res<ThrusterTexture>,
     mut enemy_count: ResMut<EnemyCount>,
     mut formation_maker: ResMut<FormationMaker>,
     win_size: Res<WinSize>,
+    wave: Res<Wave>,
 ) {
-    if enemy_count.count < ENEMY_MAX {
+    if enemy_count.count < ENEMY_MAX && !wave.is_boss() {
         /*let mut rng = thread_rng();
         let w_span = win_size.width / 2. - 100.;
         let h_span = win_size.height / 2. - 100.;
@@ -49,63 +67,69 @@ fn enemy_spawn_system(
         let formation = formation_maker.make(&win_size);
         let (x, y) = formation.start;
 
-        commands.spawn((
-            SpriteBundle {
-                texture: game_texture.enemy.clone(),
-                transform: Transform {
-                    translation: Vec3::new(x, y, 10.),
-                    scale: Vec3::new(SPRITE_SCALE.0, SPRITE_SCALE.1, 1.0),
+        commands
+            .spawn((
+                SpriteBundle {
+                    texture: game_texture.enemy.image.clone(),
+                    transform: Transform {
+                        translation: Vec3::new(x, y, 10.),
+                        scale: Vec3::new(SPRITE_SCALE.0, SPRITE_SCALE.1, 1.0),
+                        ..default()
+                    },
                     ..default()
                 },
-                ..default()
-            },
-            Enemy,
-            formation,
-            SpriteSize::from(ENEMY_SIZE),
-            Name::new("Enemy"),
-        ));
+                Enemy,
+                Health::new(1),
+                formation,
+                Weapon::enemy_random(ENEMY_LASER_SPEED),
+                FireCooldown::new(wave.difficulty.next_fire_delay()),
+                SpriteSize(game_texture.enemy.size),
+                Hitbox(game_texture.enemy.hitbox),
+                Name::new("Enemy"),
+            ))
+            .with_children(|parent| {
+                // enemies face down, their engines glow on top
+                let nozzle = Vec3::new(0., game_texture.enemy.size.y / 2. - 6., -1.);
+                spawn_thruster(parent, &thruster, "idle", nozzle, 1., true);
+            });
         enemy_count.count += 1;
-        println!("Created Enemy count: {:?}", enemy_count.count);
     }
 }
 
-fn enemy_fire_criteria() -> bool {
-    thread_rng().gen_bool(1. / 60.0)
-}
-
 fn enemy_fire_system(
-    mut commands: Commands,
-    game_texture: Res<GameTexture>,
-    enemy_query: Query<&Transform, With<Enemy>>,
+    time: Res<Time>,
+    wave: Res<Wave>,
+    laser_query: Query<(), (With<Laser>, With<FromEnemy>)>,
+    mut enemy_query: Query<(&mut Weapon, &mut FireCooldown, Option<&FireInterval>), With<Enemy>>,
 ) {
-    enemy_query.for_each(|transform| {
-        let (x, y) = (transform.translation.x, transform.translation.y);
-        commands.spawn((
-            SpriteBundle {
-                texture: game_texture.enemy_laser.clone(),
-                transform: Transform {
-                    translation: Vec3::new(x, y - 15.0, 0.),
-                    rotation: Quat::from_rotation_x(PI),
-                    scale: Vec3::new(SPRITE_SCALE.0, SPRITE_SCALE.1, 1.0),
-                    ..default()
-                },
-                ..default()
-            },
-            Laser,
-            SpriteSize::from(ENEMY_LASER_SIZE),
-            FromEnemy,
-            Movable { auto_despawn: true },
-            Velocity { x: 0., y: -1. },
-            Name::new("Laser"),
-        ));
-    })
+    // lasers of bursts under way count as already on screen
+    let mut live_lasers = laser_query.iter().count()
+        + enemy_query
+            .iter()
+            .map(|(weapon, ..)| weapon.shots_queued())
+            .sum::<usize>();
+
+    for (mut weapon, mut cooldown, interval) in enemy_query.iter_mut() {
+        if !cooldown.0.tick(time.delta()).finished() {
+            continue;
+        }
+        // hold fire (and keep the cooldown elapsed) until the whole burst fits
+        let shots = weapon.shots_per_trigger();
+        if live_lasers + shots > wave.difficulty.max_enemy_lasers {
+            continue;
+        }
+
+        weapon.trigger = true;
+        live_lasers += shots;
+        let delay = match interval {
+            Some(interval) => interval.next_delay(),
+            None => wave.difficulty.next_fire_delay(),
+        };
+        *cooldown = FireCooldown::new(delay);
+    }
 }
 
-fn enemy_movement_system(
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut Formation), With<Enemy>>,
-) {
-    let now = time.elapsed_seconds();
+fn enemy_movement_system(mut query: Query<(&mut Transform, &mut Formation), With<Enemy>>) {
     for (mut transform, mut formation) in query.iter_mut() {
         let (x_org, y_org) = (transform.translation.x, transform.translation.y);
         let max_distance = TIME_STEP * formation.speed;
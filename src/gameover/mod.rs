@@ -0,0 +1,120 @@
+use crate::menu::{spawn_menu, MenuEvent};
+use crate::settings::Settings;
+use crate::{AppState, GameFont, HighScore, PlayerState, Score, PLAYER_RESPAWN_DELAY};
+use bevy::prelude::*;
+
+/// Ends the run once the last life is lost, with a restart or a way back to the title.
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(game_over_check_system.in_set(OnUpdate(AppState::InGame)))
+            .add_system(game_over_enter_system.in_schedule(OnEnter(AppState::GameOver)))
+            .add_system(game_over_cleanup_system.in_schedule(OnExit(AppState::GameOver)))
+            .add_system(game_over_menu_system.in_set(OnUpdate(AppState::GameOver)));
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum GameOverAction {
+    Restart,
+    QuitToTitle,
+}
+
+#[derive(Component)]
+struct GameOverScreen;
+
+/// The player is down for good, leave the explosion the respawn delay to play out.
+fn game_over_check_system(
+    time: Res<Time>,
+    player_state: Res<PlayerState>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let now = time.elapsed_seconds_f64();
+    if !player_state.on
+        && player_state.lives == 0
+        && player_state.died_at != -1.
+        && now > player_state.died_at + PLAYER_RESPAWN_DELAY
+    {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+fn game_over_enter_system(
+    mut commands: Commands,
+    game_font: Res<GameFont>,
+    settings: Res<Settings>,
+    score: Res<Score>,
+    high_score: Res<HighScore>,
+) {
+    let tr = |english| settings.language.tr(english);
+    let text = |value: String, font_size| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: game_font.0.clone(),
+                font_size,
+                color: Color::WHITE,
+            },
+        )
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    gap: Size::all(Val::Px(32.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            GameOverScreen,
+            Name::new("GameOverScreen"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(text(tr("GAME OVER").into(), 40.));
+            parent.spawn(text(format!("{}  {}", tr("SCORE"), score.value), 20.));
+            if score.value > high_score.run_start {
+                parent.spawn(text(tr("NEW HIGH SCORE").into(), 16.));
+            }
+            spawn_menu(
+                parent,
+                &game_font.0,
+                vec![
+                    (tr("RESTART"), GameOverAction::Restart),
+                    (tr("QUIT TO TITLE"), GameOverAction::QuitToTitle),
+                ],
+            );
+        });
+}
+
+fn game_over_cleanup_system(mut commands: Commands, query: Query<Entity, With<GameOverScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn game_over_menu_system(
+    mut menu_events: EventReader<MenuEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+    action_query: Query<&GameOverAction>,
+) {
+    for event in menu_events.iter() {
+        let action = match event {
+            MenuEvent::Confirm { item } => action_query.get(*item).ok().copied(),
+            MenuEvent::Adjust { .. } => None,
+            MenuEvent::Back => Some(GameOverAction::QuitToTitle),
+        };
+        match action {
+            // a new run with the same ship and mode, `run_setup_system` resets it
+            Some(GameOverAction::Restart) => next_state.set(AppState::InGame),
+            Some(GameOverAction::QuitToTitle) => next_state.set(AppState::Title),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::player::spawn_meters;
+use crate::powerup::{ActivePowerUps, PowerUpKind};
+use crate::settings::Settings;
+use crate::ship::SelectedShip;
+use crate::wave::{Wave, WaveStarted};
+use crate::{AppState, GameFont, GameTexture, HighScore, PlayerState, Score, PLAYFIELD_SIZE};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowResized};
+
+/// height of a life icon, in logical pixels
+const LIFE_ICON_HEIGHT: f32 = 16.;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(hud_setup_system.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(hud_cleanup_system.in_schedule(OnExit(AppState::InGame)))
+            .add_system(hud_scale_system)
+            .add_systems(
+                (
+                    score_hud_system,
+                    wave_hud_system,
+                    lives_hud_system,
+                    bomb_hud_system,
+                    powerup_hud_system,
+                )
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
+    }
+}
+
+/// Root of the HUD. The HUD systems only touch its texts when what they show
+/// changed, or when the HUD was just (re)built.
+#[derive(Component)]
+struct Hud;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct HighScoreText;
+
+#[derive(Component)]
+struct WaveText;
+
+#[derive(Component)]
+struct LivesRow;
+
+#[derive(Component)]
+struct BombText;
+
+#[derive(Component)]
+struct PowerUpText;
+
+fn hud_setup_system(mut commands: Commands, game_font: Res<GameFont>) {
+    let text = |value: &str, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: game_font.0.clone(),
+                font_size,
+                color: Color::WHITE,
+            },
+        )
+    };
+    let row = |justify_content| NodeBundle {
+        style: Style {
+            size: Size::width(Val::Percent(100.)),
+            justify_content,
+            align_items: AlignItems::FlexStart,
+            ..default()
+        },
+        ..default()
+    };
+
+    // the HUD covers the playfield, centered like it in wider or taller windows
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Hud,
+            Name::new("Hud"),
+        ))
+        .with_children(|root| {
+            root.spawn(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(PLAYFIELD_SIZE.0), Val::Px(PLAYFIELD_SIZE.1)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn(row(JustifyContent::SpaceBetween))
+                    .with_children(|top| {
+                        top.spawn((text("", 16.), ScoreText));
+                        top.spawn((text("", 16.), HighScoreText));
+                        top.spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::FlexEnd,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|column| {
+                            column.spawn((text("", 16.), WaveText));
+                            column.spawn((
+                                text("", 14.).with_text_alignment(TextAlignment::Right),
+                                PowerUpText,
+                            ));
+                        });
+                    });
+                // the dash and heat meters on the left, lives and bombs on the right
+                let mut bottom_row = row(JustifyContent::SpaceBetween);
+                bottom_row.style.align_items = AlignItems::FlexEnd;
+                parent.spawn(bottom_row).with_children(|bottom| {
+                    bottom
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                gap: Size::all(Val::Px(4.)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(spawn_meters);
+                    bottom
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::FlexEnd,
+                                gap: Size::all(Val::Px(4.)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|column| {
+                            column.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        gap: Size::all(Val::Px(4.)),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                LivesRow,
+                            ));
+                            column.spawn((text("", 14.), BombText));
+                        });
+                });
+            });
+        });
+}
+
+fn hud_cleanup_system(mut commands: Commands, query: Query<Entity, With<Hud>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Scale the whole UI like the playfield, so it keeps its proportions at any window size.
+fn hud_scale_system(
+    mut resized: EventReader<WindowResized>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if resized.iter().last().is_none() && !ui_scale.is_added() {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let scale = (window.width() / PLAYFIELD_SIZE.0).min(window.height() / PLAYFIELD_SIZE.1);
+    ui_scale.scale = scale as f64;
+}
+
+fn score_hud_system(
+    settings: Res<Settings>,
+    score: Res<Score>,
+    high_score: Res<HighScore>,
+    hud_added: Query<(), Added<Hud>>,
+    mut score_query: Query<&mut Text, (With<ScoreText>, Without<HighScoreText>)>,
+    mut high_score_query: Query<&mut Text, (With<HighScoreText>, Without<ScoreText>)>,
+) {
+    if !score.is_changed()
+        && !high_score.is_changed()
+        && !settings.is_changed()
+        && hud_added.is_empty()
+    {
+        return;
+    }
+    for mut text in score_query.iter_mut() {
+        text.sections[0].value = format!("{} {}", settings.language.tr("SCORE"), score.value);
+    }
+    for mut text in high_score_query.iter_mut() {
+        text.sections[0].value = format!("{} {}", settings.language.tr("HI"), high_score.value);
+    }
+}
+
+fn wave_hud_system(
+    settings: Res<Settings>,
+    wave: Res<Wave>,
+    mut wave_started: EventReader<WaveStarted>,
+    hud_added: Query<(), Added<Hud>>,
+    mut query: Query<&mut Text, With<WaveText>>,
+) {
+    if wave_started.is_empty() && !settings.is_changed() && hud_added.is_empty() {
+        return;
+    }
+    wave_started.clear();
+    for mut text in query.iter_mut() {
+        let tr = |english| settings.language.tr(english);
+        text.sections[0].value = if wave.is_boss() {
+            format!("{} {} {}", tr("WAVE"), wave.number, tr("BOSS"))
+        } else {
+            format!("{} {}", tr("WAVE"), wave.number)
+        };
+    }
+}
+
+fn lives_hud_system(
+    mut commands: Commands,
+    player_state: Res<PlayerState>,
+    game_texture: Res<GameTexture>,
+    selected_ship: Res<SelectedShip>,
+    hud_added: Query<(), Added<Hud>>,
+    mut shown: Local<u32>,
+    query: Query<Entity, With<LivesRow>>,
+) {
+    // `PlayerState` changes every frame, only rebuild the icons when lives do
+    let Ok(row) = query.get_single() else {
+        return;
+    };
+    if *shown == player_state.lives && hud_added.is_empty() {
+        return;
+    }
+    *shown = player_state.lives;
+
+    let ship = &game_texture.ships[selected_ship.0];
+    let width = LIFE_ICON_HEIGHT * ship.size.x / ship.size.y;
+    commands.entity(row).despawn_descendants();
+    commands.entity(row).with_children(|parent| {
+        for _ in 0..player_state.lives {
+            parent.spawn(ImageBundle {
+                image: UiImage::new(ship.image.clone()),
+                style: Style {
+                    size: Size::new(Val::Px(width), Val::Px(LIFE_ICON_HEIGHT)),
+                    ..default()
+                },
+                ..default()
+            });
+        }
+    });
+}
+
+fn bomb_hud_system(
+    settings: Res<Settings>,
+    player_state: Res<PlayerState>,
+    hud_added: Query<(), Added<Hud>>,
+    mut shown: Local<u32>,
+    mut query: Query<&mut Text, With<BombText>>,
+) {
+    if *shown == player_state.bombs && !settings.is_changed() && hud_added.is_empty() {
+        return;
+    }
+    *shown = player_state.bombs;
+    for mut text in query.iter_mut() {
+        let bombs = "*".repeat(player_state.bombs as usize);
+        text.sections[0].value = format!("{} {}", settings.language.tr("BOMB"), bombs);
+    }
+}
+
+fn powerup_hud_system(
+    settings: Res<Settings>,
+    active: Res<ActivePowerUps>,
+    hud_added: Query<(), Added<Hud>>,
+    mut query: Query<&mut Text, With<PowerUpText>>,
+) {
+    if !active.is_changed() && !settings.is_changed() && hud_added.is_empty() {
+        return;
+    }
+    let tr = |english| settings.language.tr(english);
+    for mut text in query.iter_mut() {
+        text.sections[0].value = active
+            .remaining()
+            .iter()
+            .map(|(kind, secs)| match kind {
+                PowerUpKind::Shield => {
+                    format!(
+                        "{} x{} {:>4.1}",
+                        tr(kind.name()),
+                        active.shield_charges,
+                        secs
+                    )
+                }
+                _ => format!("{} {:>4.1}", tr(kind.name()), secs),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
@@ -0,0 +1,235 @@
+use crate::manifest::SpriteManifest;
+use crate::menu::{MenuInput, MenuInputSet};
+use crate::settings::Settings;
+use crate::{AppState, GameFont, GameTexture, SpriteManifestHandle};
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+const BAR_SIZE: (f32, f32) = (300., 16.);
+/// checker colors of the placeholder textures
+const PLACEHOLDER_COLORS: [[u8; 4]; 2] = [[255, 0, 255, 255], [40, 0, 40, 255]];
+const PLACEHOLDER_CHECKER: usize = 8;
+
+/// Holds the game on a loading screen until the sprite manifest and every
+/// `GameTexture` image it names are loaded. Images that fail are swapped for
+/// generated placeholders and listed on screen. Only `GameTexture` is tracked:
+/// the wave background images load in game when their wave comes up, a missing
+/// one only leaves the stars.
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(loading_setup_system.in_schedule(OnEnter(AppState::Loading)))
+            .add_system(
+                loading_system
+                    .after(MenuInputSet)
+                    .in_set(OnUpdate(AppState::Loading)),
+            )
+            .add_system(loading_cleanup_system.in_schedule(OnExit(AppState::Loading)));
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct ProgressBar;
+
+#[derive(Component)]
+struct LoadingText;
+
+fn loading_setup_system(mut commands: Commands, game_font: Res<GameFont>, settings: Res<Settings>) {
+    let text_style = |font_size: f32| TextStyle {
+        font: game_font.0.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    gap: Size::all(Val::Px(24.)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.04, 0.04, 0.04).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            LoadingScreen,
+            Name::new("LoadingScreen"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                settings.language.tr("LOADING"),
+                text_style(28.),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(BAR_SIZE.0), Val::Px(BAR_SIZE.1)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.), Val::Percent(100.)),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.3, 0.6, 1.).into(),
+                            ..default()
+                        },
+                        ProgressBar,
+                    ));
+                });
+            parent.spawn((
+                TextBundle::from_section("", text_style(14.))
+                    .with_text_alignment(TextAlignment::Center),
+                LoadingText,
+            ));
+        });
+}
+
+/// Build `GameTexture` from the manifest, follow the load of every texture, replace
+/// the failed ones, and move on to the title once all are settled. Failures stay
+/// on screen until confirmed.
+fn loading_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    menu_input: Res<MenuInput>,
+    settings: Res<Settings>,
+    manifest_handle: Res<SpriteManifestHandle>,
+    manifests: Res<Assets<SpriteManifest>>,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    game_texture: Option<ResMut<GameTexture>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut failed: Local<Vec<String>>,
+    mut bar_query: Query<&mut Style, With<ProgressBar>>,
+    mut text_query: Query<&mut Text, With<LoadingText>>,
+) {
+    let Some(mut game_texture) = game_texture else {
+        // the builtin manifest keeps the game playable when the file is broken
+        let builtin;
+        let manifest = match asset_server.get_load_state(&manifest_handle.0) {
+            LoadState::Loaded => manifests.get(&manifest_handle.0).unwrap(),
+            LoadState::Failed => {
+                failed.push(asset_path(&asset_server, &manifest_handle.0));
+                builtin = SpriteManifest::builtin();
+                &builtin
+            }
+            _ => return,
+        };
+        let game_texture =
+            GameTexture::from_manifest(manifest, &asset_server, &mut texture_atlases, &mut failed);
+        commands.insert_resource(game_texture);
+        return;
+    };
+
+    let GameTexture {
+        ships,
+        player_laser,
+        enemy,
+        enemy_laser,
+        explosion,
+    } = &mut *game_texture;
+    let sprites: Vec<_> = ships
+        .iter_mut()
+        .chain([player_laser, enemy, enemy_laser])
+        .collect();
+    let total = sprites.len() + 1;
+
+    // placeholders are in `images` right away, they count as loaded
+    let mut settled = 0;
+    for sprite in sprites {
+        if images.contains(&sprite.image) {
+            settled += 1;
+        } else if asset_server.get_load_state(&sprite.image) == LoadState::Failed {
+            failed.push(asset_path(&asset_server, &sprite.image));
+            sprite.image = images.add(placeholder(sprite.size));
+            settled += 1;
+        }
+    }
+    let sheet = texture_atlases.get(&explosion.atlas).unwrap();
+    if images.contains(&sheet.texture) {
+        settled += 1;
+    } else if asset_server.get_load_state(&sheet.texture) == LoadState::Failed {
+        failed.push(asset_path(&asset_server, &sheet.texture));
+        let image = images.add(placeholder(sheet.size));
+        texture_atlases.get_mut(&explosion.atlas).unwrap().texture = image;
+        settled += 1;
+    }
+
+    for mut style in bar_query.iter_mut() {
+        style.size.width = Val::Percent(100. * settled as f32 / total as f32);
+    }
+    if settled < total {
+        return;
+    }
+    if failed.is_empty() {
+        next_state.set(AppState::Title);
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value.is_empty() {
+            for path in failed.iter() {
+                warn!("Could not load {path}, using a fallback");
+            }
+            let tr = |english| settings.language.tr(english);
+            text.sections[0].value = format!(
+                "{}\n{}\n\n{}",
+                tr("COULD NOT LOAD"),
+                failed.join("\n"),
+                tr("PRESS ENTER TO CONTINUE")
+            );
+            text.sections[0].style.color = Color::rgb(1., 0.4, 0.4);
+        }
+    }
+    if menu_input.confirm {
+        next_state.set(AppState::Title);
+    }
+}
+
+fn loading_cleanup_system(mut commands: Commands, query: Query<Entity, With<LoadingScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn asset_path<T: bevy::asset::Asset>(asset_server: &AssetServer, handle: &Handle<T>) -> String {
+    asset_server.get_handle_path(handle).map_or_else(
+        || "unknown asset".into(),
+        |path| path.path().display().to_string(),
+    )
+}
+
+/// Magenta checkerboard of `size` pixels, hard to miss in game.
+fn placeholder(size: Vec2) -> Image {
+    let (width, height) = (size.x as usize, size.y as usize);
+    let mut data = vec![0; width * height * 4];
+    for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i % width, i / width);
+        let checker = (x / PLACEHOLDER_CHECKER + y / PLACEHOLDER_CHECKER) % 2;
+        pixel.copy_from_slice(&PLACEHOLDER_COLORS[checker]);
+    }
+    Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
@@ -1,57 +1,276 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
 use bevy::math::Vec3Swizzles;
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
 use bevy::sprite::collide_aabb::collide;
-use bevy::window::PrimaryWindow;
-use bevy_inspector_egui::prelude::*;
-use bevy_inspector_egui::quick::{ResourceInspectorPlugin, WorldInspectorPlugin};
-use std::any::Any;
+use std::f32::consts::PI;
 
+mod animation;
+mod background;
+mod bomb;
+mod bunker;
+mod collision;
 mod components;
 mod enemy;
+mod gameover;
+mod hud;
+mod loading;
+mod manifest;
+mod menu;
+mod particles;
+mod pause;
 mod player;
+mod powerup;
+mod settings;
+mod ship;
+mod title;
+mod ufo;
+mod wave;
+mod weapon;
 
 use crate::components::{
-    Enemy, Explosion, ExplosionTimer, ExplosionToSpawn, FromEnemy, FromPlayer, Laser, Movable,
-    Player, SpriteSize, Velocity,
+    Damage, Destructible, Enemy, ExplosionToSpawn, FaceVelocity, FloatingText, FromEnemy,
+    FromPlayer, Health, Hitbox, Homing, Laser, Lifetime, Movable, Player, PowerUpToSpawn,
+    SpriteSize, Velocity,
 };
+use animation::{Animation, AnimationClips, AnimationPlugin};
+use background::BackgroundPlugin;
+use bomb::BombPlugin;
+use bunker::BunkerPlugin;
+use collision::{world_extent, SpatialGrid};
+use enemy::boss::{BossPart, ChainExplosion};
 use enemy::EnemyPlugin;
+use gameover::GameOverPlugin;
+use hud::HudPlugin;
+use loading::LoadingPlugin;
+use manifest::{ManifestPlugin, SpriteManifest};
+use menu::MenuPlugin;
+use particles::{ParticlePlugin, Particles};
+use pause::PausePlugin;
 use player::PlayerPlugin;
-
-const PLAYER_SPRITE: &str = "player_a_01.png";
-const PLAYER_SIZE: (f32, f32) = (144., 75.);
-const PLAYER_LASER_SPRITE: &str = "laser_a_01.png";
-const PLAYER_LASER_SIZE: (f32, f32) = (9., 54.);
-
-const ENEMY_SPRITE: &str = "enemy_a_01.png";
-const ENEMY_SIZE: (f32, f32) = (144., 75.);
-const ENEMY_LASER_SPRITE: &str = "laser_b_01.png";
-const ENEMY_LASER_SIZE: (f32, f32) = (17., 55.);
-
-const EXPLOSION_SHEET: &str = "explo_a_sheet.png";
-const EXPLOSION_LEN: usize = 16;
+use powerup::{ActivePowerUps, PowerUpPlugin};
+use rand::{thread_rng, Rng};
+use settings::{Settings, SettingsPlugin};
+use ship::ShipPlugin;
+use title::{GameMode, TitlePlugin};
+use ufo::UfoPlugin;
+use wave::{Wave, WavePlugin, WaveStarted};
+use weapon::{WeaponMode, WeaponPlugin};
+
+/// sprite paths, sizes and atlases, see `manifest`
+const SPRITE_MANIFEST: &str = "game.sprites.ron";
+/// emitter settings, see `particles`
+const PARTICLE_EFFECTS: &str = "game.particles.ron";
+
+const UFO_SOUND: &str = "ufo.wav";
+const UFO_SCALE: f32 = 0.35;
+const UFO_SPEED: f32 = 1.5;
+/// min/max seconds between two UFO passes
+const UFO_SPAWN_INTERVAL: (f32, f32) = (15., 25.);
+const UFO_BONUSES: [u32; 4] = [50, 100, 150, 300];
+
+const BUNKER_COUNT: usize = 4;
+/// bunker mask resolution, one mask pixel per texture pixel
+const BUNKER_PIXELS: (usize, usize) = (44, 32);
+const BUNKER_SCALE: f32 = 1.5;
+
+const POWERUP_DROP_CHANCE: f64 = 0.15;
+/// seconds a timed power-up lasts
+const POWERUP_DURATION: f32 = 10.;
+const POWERUP_SIZE: f32 = 24.;
+
+/// cell size of the collision broadphase, about the size of a ship
+const COLLISION_CELL_SIZE: f32 = 80.;
+/// camera offset in world units at full trauma and full shake setting
+const SCREEN_SHAKE_MAX: f32 = 12.;
+/// trauma added by each explosion, trauma is capped at 1
+const SCREEN_SHAKE_TRAUMA: f32 = 0.25;
+/// trauma lost per second
+const SCREEN_SHAKE_DECAY: f32 = 1.5;
+/// diagonal in world units of a ship whose explosion bursts at the effect's scale 1
+const EXPLOSION_BURST_SIZE: f32 = 80.;
+
+const MENU_MOVE_SOUND: &str = "menu_move.wav";
+const MENU_CONFIRM_SOUND: &str = "menu_confirm.wav";
+const MENU_BACK_SOUND: &str = "menu_back.wav";
+
+const FONT: &str = "DejaVuSansMono-Bold.ttf";
 
 const SPRITE_SCALE: (f32, f32) = (0.5, 0.5);
 
+/// logical size of the playfield, the camera fits it to any window
+const PLAYFIELD_SIZE: (f32, f32) = (500., 700.);
+const LETTERBOX_COLOR: Color = Color::BLACK;
+/// clear color outside of the game, waves have their own
+const BACKGROUND_COLOR: Color = Color::rgb(0.04, 0.04, 0.04);
+
 const TIME_STEP: f32 = 1. / 60.;
 const BASE_SPEED: f32 = 50.;
 
 const PLAYER_RESPAWN_DELAY: f64 = 2.;
+/// seconds a respawned ship blinks and can't be hit
+const PLAYER_RESPAWN_INVULNERABILITY: f64 = 2.5;
+const PLAYER_BOMBS: u32 = 2;
+const PLAYER_BOMBS_MAX: u32 = 5;
+const BOMB_DAMAGE: u32 = 5;
+/// seconds the player can't be hit after dropping a bomb
+const BOMB_INVULNERABILITY: f64 = 1.5;
+const PLAYER_LIVES: u32 = 3;
+const PLAYER_LIVES_MAX: u32 = 5;
+const PLAYER_SPEED_BOOST: f32 = 1.6;
+const PLAYER_RAPID_FIRE_INTERVAL: f64 = 0.12;
+/// angle between the center laser and the side lasers of a spread shot
+const PLAYER_SPREAD_ANGLE: f32 = 0.2;
+const ENEMY_POINTS: u32 = 10;
 const ENEMY_MAX: u32 = 2;
 const FORMATION_MEMBERS_MAX: u32 = 2;
 
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    Loading,
+    Title,
+    ShipSelect,
+    InGame,
+    /// the last life is lost
+    GameOver,
+}
+
+/// Size of the playfield in world units, it doesn't follow the window.
 #[derive(Resource, Debug)]
 pub struct WinSize {
     pub width: f32,
     pub height: f32,
 }
 
+/// An image and its sizes from the sprite manifest, in pixels.
+pub struct GameSprite {
+    pub image: Handle<Image>,
+    pub size: Vec2,
+    pub hitbox: Vec2,
+}
+
+/// A sprite sheet and its named animations.
+pub struct GameAtlas {
+    pub atlas: Handle<TextureAtlas>,
+    pub animations: AnimationClips,
+}
+
+/// Built by the loading screen once the sprite manifest is loaded.
 #[derive(Resource)]
 pub struct GameTexture {
-    player: Handle<Image>,
-    player_laser: Handle<Image>,
-    enemy: Handle<Image>,
-    enemy_laser: Handle<Image>,
-    explosion: Handle<TextureAtlas>,
+    /// one per entry of `SHIPS`
+    ships: Vec<GameSprite>,
+    player_laser: GameSprite,
+    enemy: GameSprite,
+    enemy_laser: GameSprite,
+    explosion: GameAtlas,
+}
+
+/// Handle of the sprite manifest, until `GameTexture` is built from it.
+#[derive(Resource)]
+pub struct SpriteManifestHandle(Handle<SpriteManifest>);
+
+/// Damage dealt to an enemy by any player weapon.
+pub struct EnemyHit {
+    pub enemy: Entity,
+    pub damage: u32,
+}
+
+#[derive(Resource)]
+pub struct GameAudio {
+    ufo: Handle<AudioSource>,
+    menu_move: Handle<AudioSource>,
+    menu_confirm: Handle<AudioSource>,
+    menu_back: Handle<AudioSource>,
+}
+
+#[derive(Resource)]
+pub struct GameFont(Handle<Font>);
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Score {
+    value: u32,
+}
+
+/// Best score of the session.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct HighScore {
+    value: u32,
+    /// best score when the current run started, beating it is a new high score
+    run_start: u32,
+}
+
+/// Camera shake left to play, from 0 to 1.
+#[derive(Resource, Default)]
+pub struct ScreenShake {
+    pub trauma: f32,
+}
+
+/// Tuning of the player's gun.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct FireSettings {
+    /// keep firing while the fire key is held
+    pub autofire: bool,
+    /// seconds between two volleys while autofiring
+    pub autofire_interval: f64,
+    /// minimum seconds between two volleys, however fast the key is tapped
+    pub min_interval: f64,
+    /// enable the heat meter
+    pub overheat: bool,
+    /// heat added by each volley, the gun locks when heat reaches 1
+    pub heat_per_shot: f32,
+    /// heat lost per second
+    pub cooling_rate: f32,
+    /// heat under which a locked gun unlocks
+    pub overheat_recovery: f32,
+}
+
+impl Default for FireSettings {
+    fn default() -> Self {
+        Self {
+            autofire: true,
+            autofire_interval: 0.25,
+            min_interval: 0.1,
+            overheat: true,
+            heat_per_shot: 0.08,
+            cooling_rate: 0.45,
+            overheat_recovery: 0.25,
+        }
+    }
+}
+
+/// Where the player ship may go.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, FromReflect)]
+pub enum MovementRegion {
+    /// the bottom part of the screen, as a fraction of its height
+    BottomBand(f32),
+    FullScreen,
+}
+
+/// Feel of the player's ship.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct MovementSettings {
+    pub region: MovementRegion,
+    /// speed gained per second while a direction is held, 0 for instant movement
+    pub acceleration: f32,
+    /// fraction of its speed the ship loses per second once released
+    pub drag: f32,
+}
+
+impl Default for MovementSettings {
+    fn default() -> Self {
+        Self {
+            region: MovementRegion::BottomBand(0.35),
+            acceleration: 0.,
+            drag: 6.,
+        }
+    }
 }
 
 #[derive(Resource, Reflect, Default)]
@@ -64,14 +283,38 @@ struct EnemyCount {
 #[reflect(Resource)]
 struct PlayerState {
     on: bool,
-    last_shot: f64,
+    /// time the player was last shot down, -1 when alive
+    died_at: f64,
+    /// time of the player's last volley
+    last_fire: f64,
+    /// gun heat, the gun locks at 1 until it cools down
+    heat: f32,
+    overheated: bool,
+    lives: u32,
+    weapon_mode: WeaponMode,
+    /// charge of the charge shot, from 0 to 1
+    charge: f32,
+    bombs: u32,
+    /// enemy lasers go through the player until this time
+    invulnerable_until: f64,
+    /// time of the player's last dash
+    last_dash: f64,
 }
 
 impl Default for PlayerState {
     fn default() -> Self {
         Self {
             on: false,
-            last_shot: -1.,
+            died_at: -1.,
+            last_fire: -1.,
+            heat: 0.,
+            overheated: false,
+            lives: PLAYER_LIVES,
+            weapon_mode: WeaponMode::default(),
+            charge: 0.,
+            bombs: PLAYER_BOMBS,
+            invulnerable_until: -1.,
+            last_dash: f64::MIN,
         }
     }
 }
@@ -79,160 +322,448 @@ impl Default for PlayerState {
 impl PlayerState {
     pub fn shot(&mut self, time: f64) {
         self.on = false;
-        self.last_shot = time;
+        self.died_at = time;
+        self.lives = self.lives.saturating_sub(1);
+    }
+    pub fn is_invulnerable(&self, time: f64) -> bool {
+        time < self.invulnerable_until
     }
     pub fn spawned(&mut self) {
         self.on = true;
-        self.last_shot = -1.;
+        self.died_at = -1.;
+        self.heat = 0.;
+        self.overheated = false;
+        self.charge = 0.;
     }
 }
 
 fn main() {
+    // read before building the app, so the window opens with the saved settings
+    let settings = Settings::load();
+
     App::new()
-        .insert_resource(ClearColor(Color::rgb(0.04, 0.04, 0.04)))
+        .insert_resource(ClearColor(BACKGROUND_COLOR))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Rust Invaders!".into(),
-                resolution: (500., 700.).into(),
+                resolution: settings.window_resolution(),
+                mode: settings.window_mode(),
+                present_mode: settings.present_mode(),
                 ..default()
             }),
             ..default()
         }))
         // Inspector Setup
-        //.add_plugin(WorldInspectorPlugin::new())
-        //.add_plugin(ResourceInspectorPlugin::<PlayerState>::default())
-        //.add_plugin(ResourceInspectorPlugin::<EnemyCount>::default())
+        //.add_plugin(bevy_inspector_egui::quick::WorldInspectorPlugin::new())
+        //.add_plugin(bevy_inspector_egui::quick::ResourceInspectorPlugin::<PlayerState>::default())
+        //.add_plugin(bevy_inspector_egui::quick::ResourceInspectorPlugin::<EnemyCount>::default())
+        //.add_plugin(bevy_inspector_egui::quick::ResourceInspectorPlugin::<FireSettings>::default())
+        //.add_plugin(bevy_inspector_egui::quick::ResourceInspectorPlugin::<MovementSettings>::default())
+        .insert_resource(settings)
+        .init_resource::<ScreenShake>()
+        .add_state::<AppState>()
+        .add_event::<EnemyHit>()
         .add_startup_systems((setup_camera, setup_system))
+        .add_system(run_setup_system.in_schedule(OnEnter(AppState::InGame)))
+        .add_system(run_cleanup_system.in_schedule(OnExit(AppState::InGame)))
+        .add_plugin(SettingsPlugin)
+        .add_plugin(ManifestPlugin)
+        .add_plugin(LoadingPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(ParticlePlugin)
+        .add_plugin(BackgroundPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(PausePlugin)
+        .add_plugin(GameOverPlugin)
+        .add_plugin(TitlePlugin)
+        .add_plugin(ShipPlugin)
         .add_plugin(PlayerPlugin)
         .add_plugin(EnemyPlugin)
-        .add_systems((
-            movable_system,
-            player_laser_hit_enemy_system,
-            explosion_to_spawn_system,
-            enemy_laser_hit_player_system,
-            explosion_animation_system,
-        ))
+        .add_plugin(WavePlugin)
+        .add_plugin(UfoPlugin)
+        .add_plugin(BunkerPlugin)
+        .add_plugin(PowerUpPlugin)
+        .add_plugin(WeaponPlugin)
+        .add_plugin(BombPlugin)
+        .add_plugin(HudPlugin)
+        .add_systems(
+            (
+                homing_system.before(movable_system),
+                movable_system,
+                lifetime_system,
+                // the lasers an interception despawned must be gone before hits are
+                // checked, or one shot could take out a laser and an enemy
+                apply_system_buffers
+                    .after(laser_intercept_system)
+                    .before(player_laser_hit_enemy_system),
+                laser_intercept_system,
+                player_laser_hit_enemy_system,
+                enemy_damage_system.after(player_laser_hit_enemy_system),
+                explosion_to_spawn_system,
+                enemy_laser_hit_player_system,
+                floating_text_system,
+                high_score_system,
+                screen_shake_system,
+            )
+                .in_set(OnUpdate(AppState::InGame)),
+        )
         .run();
 }
 
+/// The camera always shows the whole playfield, the window's extra width or
+/// height is covered by letterbox bars.
 fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: PLAYFIELD_SIZE.0,
+        min_height: PLAYFIELD_SIZE.1,
+    };
+
+    // bars are children of the camera, so they stay put while the screen shakes
+    let (width, height) = PLAYFIELD_SIZE;
+    let span = 10. * width.max(height);
+    let bars = [
+        Vec2::new(-(width + span) / 2., 0.),
+        Vec2::new((width + span) / 2., 0.),
+        Vec2::new(0., -(height + span) / 2.),
+        Vec2::new(0., (height + span) / 2.),
+    ];
+    // the visibility lets the bars inherit it from the camera
+    commands
+        .spawn((camera, VisibilityBundle::default()))
+        .with_children(|parent| {
+            for position in bars {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: LETTERBOX_COLOR,
+                        custom_size: Some(Vec2::splat(span)),
+                        ..default()
+                    },
+                    // the camera sits at z 999.9, keep the bars in front of everything
+                    transform: Transform::from_xyz(position.x, position.y, -1.),
+                    ..default()
+                });
+            }
+        });
+}
+
+fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WinSize {
+        width: PLAYFIELD_SIZE.0,
+        height: PLAYFIELD_SIZE.1,
+    });
+
+    commands.insert_resource(SpriteManifestHandle(asset_server.load(SPRITE_MANIFEST)));
+    commands.insert_resource(GameAudio {
+        ufo: asset_server.load(UFO_SOUND),
+        menu_move: asset_server.load(MENU_MOVE_SOUND),
+        menu_confirm: asset_server.load(MENU_CONFIRM_SOUND),
+        menu_back: asset_server.load(MENU_BACK_SOUND),
+    });
+    commands.insert_resource(GameFont(asset_server.load(FONT)));
+    commands.insert_resource(EnemyCount { count: 0 });
+    commands.insert_resource(Score::default());
+    commands.insert_resource(HighScore::default());
 }
 
-fn setup_system(
+/// Start a fresh run, from the ship select screen or a restart.
+fn run_setup_system(
     mut commands: Commands,
-    query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    game_mode: Res<GameMode>,
+    mut player_state: ResMut<PlayerState>,
+    mut high_score: ResMut<HighScore>,
+    mut wave_started: EventWriter<WaveStarted>,
 ) {
-    let Ok(primary) = query.get_single() else { return; };
-    let (win_width, win_height) = (primary.width(), primary.height());
-
-    let win_size = WinSize {
-        width: win_width,
-        height: win_height,
+    high_score.run_start = high_score.value;
+    *player_state = PlayerState {
+        lives: game_mode.lives(),
+        bombs: game_mode.bombs(),
+        ..default()
     };
+    commands.insert_resource(Score::default());
+    commands.insert_resource(EnemyCount { count: 0 });
+    commands.insert_resource(Wave::new(1));
+    wave_started.send(WaveStarted);
+    commands.insert_resource(ActivePowerUps::default());
+    commands.insert_resource(ScreenShake::default());
+}
 
-    commands.insert_resource(win_size);
-
-    let texture_handle = asset_server.load(EXPLOSION_SHEET);
-    let texture_atlas =
-        TextureAtlas::from_grid(texture_handle, Vec2::new(64., 64.), 4, 4, None, None);
-    let explosion = texture_atlases.add(texture_atlas);
-
-    let game_texture = GameTexture {
-        player: asset_server.load(PLAYER_SPRITE),
-        player_laser: asset_server.load(PLAYER_LASER_SPRITE),
-        enemy: asset_server.load(ENEMY_SPRITE),
-        enemy_laser: asset_server.load(ENEMY_LASER_SPRITE),
-        explosion,
-    };
+/// Clear the playfield when a run ends: every sprite goes, the camera and the UI stay.
+fn run_cleanup_system(
+    mut commands: Commands,
+    query: Query<
+        Entity,
+        (
+            With<Transform>,
+            Without<Camera>,
+            Without<Node>,
+            Without<Parent>,
+        ),
+    >,
+    // pending spawn requests have no transform, but must not leak into the next run
+    request_query: Query<
+        Entity,
+        Or<(
+            With<ChainExplosion>,
+            With<ExplosionToSpawn>,
+            With<PowerUpToSpawn>,
+        )>,
+    >,
+) {
+    for entity in query.iter().chain(request_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
 
-    commands.insert_resource(game_texture);
-    commands.insert_resource(EnemyCount { count: 0 });
+fn homing_system(
+    mut query: Query<(&Transform, &mut Velocity, &mut Homing)>,
+    enemy_query: Query<(Entity, &GlobalTransform), With<Enemy>>,
+) {
+    for (transform, mut velocity, mut homing) in query.iter_mut() {
+        let position = transform.translation.xy();
+        let target = homing
+            .target
+            .and_then(|target| enemy_query.get(target).ok())
+            .or_else(|| {
+                enemy_query.iter().min_by(|(_, a), (_, b)| {
+                    let a = a.translation().xy().distance_squared(position);
+                    let b = b.translation().xy().distance_squared(position);
+                    a.total_cmp(&b)
+                })
+            });
+        homing.target = target.map(|(entity, _)| entity);
+        let Some((_, target_transform)) = target else {
+            continue;
+        };
+
+        // rotate the heading toward the target, no faster than the turn rate
+        let heading = Vec2::new(velocity.x, velocity.y);
+        let wanted = target_transform.translation().xy() - position;
+        let max_turn = homing.turn_rate * TIME_STEP;
+        let turn = heading.angle_between(wanted).clamp(-max_turn, max_turn);
+        if turn.is_nan() {
+            continue;
+        }
+        let heading = Vec2::from_angle(turn).rotate(heading);
+        velocity.x = heading.x;
+        velocity.y = heading.y;
+    }
 }
 
 fn movable_system(
     mut commands: Commands,
     win_size: Res<WinSize>,
-    mut query: Query<(Entity, &Velocity, &mut Transform, &Movable)>,
+    mut query: Query<(
+        Entity,
+        &Velocity,
+        &mut Transform,
+        &Movable,
+        Option<&FaceVelocity>,
+    )>,
 ) {
-    query.for_each_mut(|(entity, velocity, mut transform, moveable)| {
-        transform.translation.x += velocity.x * TIME_STEP * BASE_SPEED;
-        transform.translation.y += velocity.y * TIME_STEP * BASE_SPEED;
-
-        if moveable.auto_despawn {
-            let margin: f32 = 200.;
-            if transform.translation.x > win_size.width / 2. + margin
-                || transform.translation.x < -win_size.width / 2. - margin
-                || transform.translation.y > win_size.height / 2. + margin
-                || transform.translation.y < -win_size.height / 2. - margin
-            {
-                // println!("->> despawned {entity:?}");
-                // println!(
-                //     "->> translation {:?} win_size {:?}",
-                //     transform.translation, win_size
-                // );
-                commands.entity(entity).despawn();
+    query.for_each_mut(
+        |(entity, velocity, mut transform, moveable, face_velocity)| {
+            transform.translation.x += velocity.x * TIME_STEP * BASE_SPEED;
+            transform.translation.y += velocity.y * TIME_STEP * BASE_SPEED;
+            if face_velocity.is_some() && (velocity.x != 0. || velocity.y != 0.) {
+                transform.rotation = Quat::from_rotation_z(velocity.y.atan2(velocity.x) - PI / 2.);
+            }
+
+            if moveable.auto_despawn {
+                let margin: f32 = 200.;
+                if transform.translation.x > win_size.width / 2. + margin
+                    || transform.translation.x < -win_size.width / 2. - margin
+                    || transform.translation.y > win_size.height / 2. + margin
+                    || transform.translation.y < -win_size.height / 2. - margin
+                {
+                    // println!("->> despawned {entity:?}");
+                    // println!(
+                    //     "->> translation {:?} win_size {:?}",
+                    //     transform.translation, win_size
+                    // );
+                    commands.entity(entity).despawn_recursive();
+                }
             }
+        },
+    );
+}
+
+fn lifetime_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Lifetime)>,
+) {
+    for (entity, mut lifetime) in query.iter_mut() {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
         }
-    });
+    }
 }
 
 fn player_laser_hit_enemy_system(
     mut commands: Commands,
-    mut enemy_count: ResMut<EnemyCount>,
-    laser_query: Query<(Entity, &Transform, &SpriteSize), (With<Laser>, With<FromPlayer>)>,
-    enemy_query: Query<(Entity, &Transform, &SpriteSize), With<Enemy>>,
+    mut enemy_hits: EventWriter<EnemyHit>,
+    laser_query: Query<
+        (Entity, &Transform, &Hitbox, Option<&Damage>),
+        (With<Laser>, With<FromPlayer>),
+    >,
+    enemy_query: Query<(Entity, &GlobalTransform, &Hitbox), With<Enemy>>,
 ) {
-    laser_query.for_each(|(laser_entity, laser_transform, laser_sprite_size)| {
-        let laser_scale = laser_transform.scale.xy();
-        enemy_query.for_each(|(enemy_entity, enemy_transform, enemy_sprite_size)| {
-            let enemy_scale = enemy_transform.scale.xy();
-            // determine if collision
-            let collision = collide(
-                laser_transform.translation,
-                laser_sprite_size.0 * laser_scale,
-                enemy_transform.translation,
-                enemy_sprite_size.0 * enemy_scale,
+    let mut enemies = SpatialGrid::new(COLLISION_CELL_SIZE);
+    for (enemy_entity, enemy_transform, enemy_hitbox) in enemy_query.iter() {
+        // boss parts are children, so use their world position and scale
+        let enemy_transform = enemy_transform.compute_transform();
+        enemies.insert(
+            enemy_entity,
+            enemy_transform.translation.xy(),
+            enemy_hitbox.0 * enemy_transform.scale.xy(),
+        );
+    }
+
+    for (laser_entity, laser_transform, laser_hitbox, damage) in laser_query.iter() {
+        let hits = enemies.query(
+            laser_transform.translation.xy(),
+            world_extent(laser_hitbox.0, laser_transform),
+        );
+
+        //perform collision
+        if let Some(enemy_entity) = hits.first() {
+            commands.entity(laser_entity).despawn();
+            enemy_hits.send(EnemyHit {
+                enemy: *enemy_entity,
+                damage: damage.map_or(1, |damage| damage.0),
+            });
+        }
+    }
+}
+
+/// Player shots destroy the enemy lasers marked `Destructible` they run into.
+fn laser_intercept_system(
+    mut commands: Commands,
+    mut particles: Particles,
+    player_laser_query: Query<(Entity, &Transform, &Hitbox), (With<Laser>, With<FromPlayer>)>,
+    enemy_laser_query: Query<
+        (Entity, &Transform, &Hitbox),
+        (With<Laser>, With<FromEnemy>, With<Destructible>),
+    >,
+) {
+    let mut enemy_lasers = SpatialGrid::new(COLLISION_CELL_SIZE);
+    for (laser_entity, laser_transform, laser_hitbox) in enemy_laser_query.iter() {
+        enemy_lasers.insert(
+            laser_entity,
+            laser_transform.translation.xy(),
+            world_extent(laser_hitbox.0, laser_transform),
+        );
+    }
+
+    let mut destroyed = Vec::new();
+    for (player_laser, laser_transform, laser_hitbox) in player_laser_query.iter() {
+        let hit = enemy_lasers
+            .query(
+                laser_transform.translation.xy(),
+                world_extent(laser_hitbox.0, laser_transform),
+            )
+            .into_iter()
+            .find(|enemy_laser| !destroyed.contains(enemy_laser));
+        if let Some(enemy_laser) = hit {
+            destroyed.push(enemy_laser);
+            commands.entity(player_laser).despawn_recursive();
+            commands.entity(enemy_laser).despawn();
+            let (_, enemy_transform, _) = enemy_laser_query.get(enemy_laser).unwrap();
+            particles.burst(
+                "sparks",
+                (laser_transform.translation.xy() + enemy_transform.translation.xy()) / 2.,
+                1.,
             );
+        }
+    }
+}
 
-            //perform collision
-            if collision.is_some() {
-                commands.entity(laser_entity).despawn();
-                commands.entity(enemy_entity).despawn();
-                commands.spawn(ExplosionToSpawn(enemy_transform.translation));
-                println!("Despawn Enemy count: {:?}", enemy_count.count);
+/// Apply damage to enemies and handle the ones that are destroyed.
+fn enemy_damage_system(
+    mut commands: Commands,
+    mut enemy_hits: EventReader<EnemyHit>,
+    mut enemy_count: ResMut<EnemyCount>,
+    mut wave: ResMut<Wave>,
+    mut score: ResMut<Score>,
+    mut enemy_query: Query<
+        (
+            &GlobalTransform,
+            &SpriteSize,
+            &mut Health,
+            Option<&BossPart>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for hit in enemy_hits.iter() {
+        let Ok((enemy_transform, enemy_size, mut enemy_health, boss_part)) =
+            enemy_query.get_mut(hit.enemy)
+        else {
+            continue;
+        };
+        if enemy_health.is_dead() {
+            continue;
+        }
+
+        enemy_health.damage(hit.damage);
+        if enemy_health.is_dead() {
+            // boss parts are children, so use their world position and scale
+            let enemy_transform = enemy_transform.compute_transform();
+            let translation = enemy_transform.translation;
+            commands.spawn(ExplosionToSpawn {
+                translation,
+                size: enemy_size.0 * enemy_transform.scale.xy(),
+            });
+            if thread_rng().gen_bool(POWERUP_DROP_CHANCE) {
+                commands.spawn(PowerUpToSpawn(translation));
+            }
+            score.value += boss_part.map_or(ENEMY_POINTS, |part| part.points());
+            // destroyed boss parts are handled by the boss systems
+            if boss_part.is_none() {
+                commands.entity(hit.enemy).despawn_recursive();
                 enemy_count.count -= 1;
+                wave.register_kill();
             }
-        })
-    })
+        }
+    }
 }
 
 fn enemy_laser_hit_player_system(
     mut commands: Commands,
     mut player_state: ResMut<PlayerState>,
+    mut active_powerups: ResMut<ActivePowerUps>,
     time: Res<Time>,
-    laser_query: Query<(Entity, &Transform, &SpriteSize), (With<Laser>, With<FromEnemy>)>,
-    player_query: Query<(Entity, &Transform, &SpriteSize), With<Player>>,
+    laser_query: Query<(Entity, &Transform, &Hitbox), (With<Laser>, With<FromEnemy>)>,
+    player_query: Query<(Entity, &Transform, &Hitbox, &SpriteSize), With<Player>>,
 ) {
-    if let Ok((player_entity, player_transform, player_size)) = player_query.get_single() {
+    if player_state.is_invulnerable(time.elapsed_seconds_f64()) {
+        return;
+    }
+    if let Ok((player_entity, player_transform, player_size, player_sprite_size)) =
+        player_query.get_single()
+    {
         let player_scale = player_transform.scale.xy();
-        for (laser_entity, laser_transform, laser_sprite_size) in laser_query.iter() {
-            let laser_scale = laser_transform.scale.xy();
-
+        for (laser_entity, laser_transform, laser_hitbox) in laser_query.iter() {
             let collision = collide(
                 player_transform.translation,
                 player_size.0 * player_scale,
                 laser_transform.translation,
-                laser_sprite_size.0 * laser_scale,
+                world_extent(laser_hitbox.0, laser_transform),
             );
 
             if collision.is_some() {
                 commands.entity(laser_entity).despawn();
-                commands.entity(player_entity).despawn();
+                if active_powerups.absorb_hit() {
+                    continue;
+                }
+                commands.entity(player_entity).despawn_recursive();
                 player_state.shot(time.elapsed_seconds_f64());
-                commands.spawn(ExplosionToSpawn(player_transform.translation));
+                active_powerups.clear();
+                commands.spawn(ExplosionToSpawn {
+                    translation: player_transform.translation,
+                    size: player_sprite_size.0 * player_scale,
+                });
                 break;
             }
         }
@@ -242,37 +773,95 @@ fn enemy_laser_hit_player_system(
 fn explosion_to_spawn_system(
     mut commands: Commands,
     game_texture: Res<GameTexture>,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut particles: Particles,
     query: Query<(Entity, &ExplosionToSpawn), With<ExplosionToSpawn>>,
 ) {
-    query.for_each(|(explosion_spawn_entity, explosion_to_transform)| {
+    query.for_each(|(explosion_spawn_entity, explosion_to_spawn)| {
+        screen_shake.trauma = (screen_shake.trauma + SCREEN_SHAKE_TRAUMA).min(1.);
+        // the manifest's clip despawns the explosion once played
+        let animation = Animation::new(&game_texture.explosion.animations, "explode");
         commands.spawn((
             SpriteSheetBundle {
-                texture_atlas: game_texture.explosion.clone(),
-                transform: Transform::from_translation(explosion_to_transform.0),
+                texture_atlas: game_texture.explosion.atlas.clone(),
+                sprite: TextureAtlasSprite::new(animation.index()),
+                transform: Transform::from_translation(explosion_to_spawn.translation),
                 ..default()
             },
-            Explosion,
-            ExplosionTimer::default(),
+            animation,
+            Name::new("Explosion"),
         ));
+        particles.burst(
+            "explosion",
+            explosion_to_spawn.translation.xy(),
+            explosion_to_spawn.size.length() / EXPLOSION_BURST_SIZE,
+        );
 
         commands.entity(explosion_spawn_entity).despawn();
     })
 }
 
-fn explosion_animation_system(
+/// Jitter the camera while there is trauma left, scaled by the screen shake setting.
+fn screen_shake_system(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut query: Query<&mut Transform, With<Camera2d>>,
+) {
+    if screen_shake.trauma <= 0. && !screen_shake.is_changed() {
+        return;
+    }
+    screen_shake.trauma = (screen_shake.trauma - SCREEN_SHAKE_DECAY * time.delta_seconds()).max(0.);
+    let mut rng = thread_rng();
+    let amount = screen_shake.trauma.powi(2) * settings.screen_shake as f32 * SCREEN_SHAKE_MAX;
+    for mut transform in query.iter_mut() {
+        transform.translation.x = rng.gen_range(-1. ..=1.) * amount;
+        transform.translation.y = rng.gen_range(-1. ..=1.) * amount;
+    }
+}
+
+fn high_score_system(score: Res<Score>, mut high_score: ResMut<HighScore>) {
+    if score.is_changed() && score.value > high_score.value {
+        high_score.value = score.value;
+    }
+}
+
+fn spawn_floating_text(
+    commands: &mut Commands,
+    game_font: &GameFont,
+    text: String,
+    translation: Vec3,
+) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    font: game_font.0.clone(),
+                    font_size: 20.,
+                    color: Color::WHITE,
+                },
+            ),
+            transform: Transform::from_xyz(translation.x, translation.y, 20.),
+            ..default()
+        },
+        FloatingText::default(),
+    ));
+}
+
+fn floating_text_system(
     mut commands: Commands,
     time: Res<Time>,
-    mut query: Query<(Entity, &mut ExplosionTimer, &mut TextureAtlasSprite), With<Explosion>>,
+    mut query: Query<(Entity, &mut FloatingText, &mut Transform, &mut Text)>,
 ) {
-    query.for_each_mut(|(explosion_entity, mut explosion_timer, mut sprite)| {
-        explosion_timer.0.tick(time.delta());
-        if explosion_timer.0.finished() {
-            sprite.index += 1;
-            if sprite.index >= EXPLOSION_LEN {
-                commands.entity(explosion_entity).despawn();
-            }
-        } else {
-            //sprite.index = (sprite.index + 1) % EXPLOSION_LEN;
+    for (entity, mut floating, mut transform, mut text) in query.iter_mut() {
+        floating.0.tick(time.delta());
+        transform.translation.y += 30. * time.delta_seconds();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(floating.0.percent_left());
         }
-    })
+        if floating.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
 }
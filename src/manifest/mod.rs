@@ -0,0 +1,265 @@
+use crate::animation::{AnimationClip, LoopMode, OnFinish};
+use crate::ship::SHIPS;
+use crate::{GameAtlas, GameSprite, GameTexture, SPRITE_MANIFEST};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// copy of the shipped manifest, used when the file can't be loaded or lacks a sprite
+const BUILTIN_MANIFEST: &str = include_str!("../../assets/game.sprites.ron");
+
+/// Registers `SpriteManifest` as an asset, loaded from `*.sprites.ron` files.
+pub struct ManifestPlugin;
+
+impl Plugin for ManifestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SpriteManifest>()
+            .init_asset_loader::<SpriteManifestLoader>();
+    }
+}
+
+/// Every sprite of the game by name, see `assets/game.sprites.ron`.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5d3f6a0e-4b6c-4f0e-9a57-2f1c1e8b7d42"]
+pub struct SpriteManifest {
+    pub sprites: HashMap<String, SpriteEntry>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SpriteEntry {
+    pub path: String,
+    /// size in pixels, of one tile for an atlas
+    pub size: (f32, f32),
+    /// collision box in pixels when smaller than `size`
+    #[serde(default)]
+    pub hitbox: Option<(f32, f32)>,
+    #[serde(default)]
+    pub atlas: Option<AtlasGrid>,
+    #[serde(default)]
+    pub animations: HashMap<String, AnimationClip>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct AtlasGrid {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl SpriteManifest {
+    pub fn builtin() -> Self {
+        ron::from_str(BUILTIN_MANIFEST).expect("the builtin sprite manifest is valid")
+    }
+}
+
+impl GameTexture {
+    /// Start loading every sprite of `manifest`. Entries it lacks, or that are not
+    /// what the game expects, come from the builtin manifest and are reported in
+    /// `problems`.
+    pub fn from_manifest(
+        manifest: &SpriteManifest,
+        asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+        problems: &mut Vec<String>,
+    ) -> Self {
+        let builtin = SpriteManifest::builtin();
+        let mut entry =
+            |name: &str, valid: fn(&SpriteEntry) -> bool| match manifest.sprites.get(name) {
+                Some(entry) if valid(entry) => entry.clone(),
+                Some(_) => {
+                    problems.push(format!("{SPRITE_MANIFEST}: invalid sprite `{name}`"));
+                    builtin.sprites[name].clone()
+                }
+                None => {
+                    problems.push(format!("{SPRITE_MANIFEST}: no sprite `{name}`"));
+                    builtin.sprites[name].clone()
+                }
+            };
+        let mut sprite = |name: &str| {
+            let entry = entry(name, SpriteEntry::is_valid);
+            GameSprite {
+                image: asset_server.load(entry.path.as_str()),
+                size: entry.size(),
+                hitbox: entry.hitbox(),
+            }
+        };
+
+        let ships = SHIPS.iter().map(|ship| sprite(ship.sprite)).collect();
+        let player_laser = sprite("player_laser");
+        let enemy = sprite("enemy");
+        let enemy_laser = sprite("enemy_laser");
+
+        let explosion = entry("explosion", SpriteEntry::is_valid_explosion);
+        let grid = explosion.atlas.unwrap();
+        let atlas = TextureAtlas::from_grid(
+            asset_server.load(explosion.path.as_str()),
+            explosion.size(),
+            grid.columns,
+            grid.rows,
+            None,
+            None,
+        );
+
+        Self {
+            ships,
+            player_laser,
+            enemy,
+            enemy_laser,
+            explosion: GameAtlas {
+                atlas: texture_atlases.add(atlas),
+                animations: Arc::new(explosion.animations),
+            },
+        }
+    }
+}
+
+impl SpriteEntry {
+    /// A positive size, a grid with tiles, and clips within its frames that
+    /// only chain to clips of the same sheet.
+    fn is_valid(&self) -> bool {
+        // a plain sprite is a single frame
+        let frames = self.atlas.map_or(1, |grid| grid.columns * grid.rows);
+        self.size.0 > 0.
+            && self.size.1 > 0.
+            && frames > 0
+            && self.animations.values().all(|clip| {
+                let chain_found = match &clip.on_finish {
+                    OnFinish::Chain(next) => self.animations.contains_key(next),
+                    _ => true,
+                };
+                clip.first <= clip.last && clip.last < frames && chain_found
+            })
+    }
+
+    /// A valid sheet whose `explode` clip plays once and despawns the explosion,
+    /// which nothing else removes.
+    fn is_valid_explosion(&self) -> bool {
+        let explode = self.animations.get("explode");
+        self.is_valid()
+            && self.atlas.is_some()
+            && explode.is_some_and(|clip| {
+                clip.mode == LoopMode::Once && clip.on_finish == OnFinish::Despawn
+            })
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.size.0, self.size.1)
+    }
+
+    pub fn hitbox(&self) -> Vec2 {
+        self.hitbox.map_or(self.size(), |(x, y)| Vec2::new(x, y))
+    }
+}
+
+#[derive(Default)]
+pub struct SpriteManifestLoader;
+
+impl AssetLoader for SpriteManifestLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let manifest = ron::de::from_bytes::<SpriteManifest>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sprites.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ron: &str) -> SpriteEntry {
+        ron::from_str(ron).unwrap()
+    }
+
+    #[test]
+    fn builtin_entries_are_valid() {
+        let manifest = SpriteManifest::builtin();
+        assert!(manifest.sprites.values().all(SpriteEntry::is_valid));
+        assert!(manifest.sprites["explosion"].is_valid_explosion());
+    }
+
+    #[test]
+    fn rejects_empty_size() {
+        assert!(!entry("(path: \"a.png\", size: (0, 10))").is_valid());
+        assert!(!entry("(path: \"a.png\", size: (10, -1))").is_valid());
+    }
+
+    #[test]
+    fn rejects_empty_grid() {
+        let sheet = |columns, rows| {
+            entry(&format!(
+                "(path: \"a.png\", size: (8, 8), atlas: Some((columns: {columns}, rows: {rows})))"
+            ))
+        };
+        assert!(sheet(2, 2).is_valid());
+        assert!(!sheet(0, 2).is_valid());
+        assert!(!sheet(2, 0).is_valid());
+    }
+
+    #[test]
+    fn rejects_clips_outside_the_grid() {
+        let sheet = |first, last| {
+            entry(&format!(
+                "(path: \"a.png\", size: (8, 8), atlas: Some((columns: 2, rows: 2)), \
+                 animations: {{\"a\": (first: {first}, last: {last}, frame_time: 0.1)}})"
+            ))
+        };
+        assert!(sheet(0, 3).is_valid());
+        assert!(sheet(2, 2).is_valid());
+        // backwards
+        assert!(!sheet(3, 1).is_valid());
+        // past the 4 frames of the grid
+        assert!(!sheet(0, 4).is_valid());
+    }
+
+    #[test]
+    fn rejects_chains_to_missing_clips() {
+        let sheet = |next| {
+            entry(&format!(
+                "(path: \"a.png\", size: (8, 8), atlas: Some((columns: 2, rows: 2)), \
+                 animations: {{\"a\": (first: 0, last: 1, frame_time: 0.1, \
+                 on_finish: Chain(\"{next}\")), \"b\": (first: 2, last: 3, frame_time: 0.1)}})"
+            ))
+        };
+        assert!(sheet("b").is_valid());
+        assert!(!sheet("missing").is_valid());
+    }
+
+    #[test]
+    fn explosion_must_despawn_itself() {
+        let explosion = |explode: &str| {
+            entry(&format!(
+                "(path: \"a.png\", size: (8, 8), atlas: Some((columns: 2, rows: 2)), \
+                 animations: {{\"explode\": (first: 0, last: 3, frame_time: 0.1, {explode})}})"
+            ))
+        };
+        assert!(explosion("on_finish: Despawn").is_valid_explosion());
+        assert!(explosion("mode: Once, on_finish: Despawn").is_valid_explosion());
+        // these would leave the explosion on screen
+        assert!(!explosion("mode: Loop, on_finish: Despawn").is_valid_explosion());
+        assert!(!explosion("mode: PingPong, on_finish: Despawn").is_valid_explosion());
+        assert!(!explosion("on_finish: Event").is_valid_explosion());
+        assert!(!explosion("").is_valid_explosion());
+        // a chain target that exists still never despawns
+        let chained = entry(
+            "(path: \"a.png\", size: (8, 8), atlas: Some((columns: 2, rows: 2)), \
+             animations: {\"explode\": (first: 0, last: 1, frame_time: 0.1, \
+             on_finish: Chain(\"smoke\")), \"smoke\": (first: 2, last: 3, frame_time: 0.1)})",
+        );
+        assert!(chained.is_valid());
+        assert!(!chained.is_valid_explosion());
+        // no explode clip at all, or not a sheet
+        assert!(!entry("(path: \"a.png\", size: (8, 8))").is_valid_explosion());
+    }
+}
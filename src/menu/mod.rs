@@ -0,0 +1,231 @@
+use crate::settings::Settings;
+use crate::GameAudio;
+use bevy::prelude::*;
+
+/// how far the left stick has to be pushed to count as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+const ITEM_COLOR: Color = Color::rgb(0.7, 0.7, 0.7);
+const FOCUS_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+
+/// Reusable vertical menus. A screen spawns one with `spawn_menu`, tagging each
+/// entry with its own action component, and reacts to `MenuEvent`s.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuInput>()
+            .add_event::<MenuEvent>()
+            .add_systems((
+                menu_input_system.in_set(MenuInputSet),
+                menu_navigation_system.after(menu_input_system),
+                menu_focus_system.after(menu_navigation_system),
+            ));
+    }
+}
+
+/// Systems reading `MenuInput` directly run after this set, so they never see
+/// the press that opened their screen.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuInputSet;
+
+/// Menu actions pressed this frame, merged from the keyboard and every gamepad.
+#[derive(Resource, Default)]
+pub struct MenuInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    /// stick direction held last frame, so holding it moves only once
+    stick: IVec2,
+}
+
+#[derive(Component)]
+pub struct Menu {
+    pub focused: usize,
+    len: usize,
+}
+
+#[derive(Component)]
+pub struct MenuItem {
+    index: usize,
+    /// shown text, screens may rewrite it to show a value
+    pub label: String,
+}
+
+pub enum MenuEvent {
+    /// `item` is the entry entity, holding the action component given to `spawn_menu`
+    Confirm {
+        item: Entity,
+    },
+    /// left (`step` -1) or right (`step` 1) on the focused entry
+    Adjust {
+        item: Entity,
+        step: i32,
+    },
+    Back,
+}
+
+/// Spawn a menu under `parent`, one text entry per `(label, action)`.
+pub fn spawn_menu<A: Component>(
+    parent: &mut ChildBuilder,
+    font: &Handle<Font>,
+    entries: Vec<(&str, A)>,
+) -> Entity {
+    let len = entries.len();
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    gap: Size::all(Val::Px(10.)),
+                    ..default()
+                },
+                ..default()
+            },
+            Menu { focused: 0, len },
+        ))
+        .with_children(|menu| {
+            for (index, (label, action)) in entries.into_iter().enumerate() {
+                menu.spawn((
+                    TextBundle::from_section(
+                        label,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 22.,
+                            color: ITEM_COLOR,
+                        },
+                    ),
+                    MenuItem {
+                        index,
+                        label: label.to_string(),
+                    },
+                    action,
+                ));
+            }
+        })
+        .id()
+}
+
+fn menu_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut menu_input: ResMut<MenuInput>,
+) {
+    let key = |keys: &[KeyCode]| keys.iter().any(|key| keyboard_input.just_pressed(*key));
+    let button = |button_type| {
+        gamepads
+            .iter()
+            .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+    };
+
+    // the stick only counts when it enters a direction
+    let mut stick = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| {
+            gamepad_axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.)
+        };
+        stick += Vec2::new(
+            axis(GamepadAxisType::LeftStickX),
+            axis(GamepadAxisType::LeftStickY),
+        );
+    }
+    let stick = IVec2::new(
+        (stick.x.abs() > STICK_THRESHOLD) as i32 * stick.x.signum() as i32,
+        (stick.y.abs() > STICK_THRESHOLD) as i32 * stick.y.signum() as i32,
+    );
+    let previous = menu_input.stick;
+    let stick_entered = |direction: IVec2| stick.dot(direction) > 0 && previous.dot(direction) <= 0;
+
+    *menu_input = MenuInput {
+        up: key(&[KeyCode::Up, KeyCode::W])
+            || button(GamepadButtonType::DPadUp)
+            || stick_entered(IVec2::Y),
+        down: key(&[KeyCode::Down, KeyCode::S])
+            || button(GamepadButtonType::DPadDown)
+            || stick_entered(IVec2::NEG_Y),
+        left: key(&[KeyCode::Left, KeyCode::A])
+            || button(GamepadButtonType::DPadLeft)
+            || stick_entered(IVec2::NEG_X),
+        right: key(&[KeyCode::Right, KeyCode::D])
+            || button(GamepadButtonType::DPadRight)
+            || stick_entered(IVec2::X),
+        confirm: key(&[KeyCode::Return, KeyCode::Space])
+            || button(GamepadButtonType::South)
+            || button(GamepadButtonType::Start),
+        back: key(&[KeyCode::Escape, KeyCode::Back]) || button(GamepadButtonType::East),
+        stick,
+    };
+}
+
+fn menu_navigation_system(
+    menu_input: Res<MenuInput>,
+    audio: Res<Audio>,
+    game_audio: Res<GameAudio>,
+    settings: Res<Settings>,
+    mut menu_events: EventWriter<MenuEvent>,
+    mut menu_query: Query<(Entity, &mut Menu)>,
+    item_query: Query<(Entity, &MenuItem, &Parent)>,
+) {
+    for (menu_entity, mut menu) in menu_query.iter_mut() {
+        if menu.len == 0 {
+            continue;
+        }
+        if menu_input.up || menu_input.down {
+            menu.focused = if menu_input.up {
+                (menu.focused + menu.len - 1) % menu.len
+            } else {
+                (menu.focused + 1) % menu.len
+            };
+            audio.play_with_settings(game_audio.menu_move.clone(), settings.sfx());
+        }
+        let focused = item_query
+            .iter()
+            .find(|(_, item, parent)| parent.get() == menu_entity && item.index == menu.focused)
+            .map(|(item, ..)| item);
+        if let Some(item) = focused {
+            if menu_input.left || menu_input.right {
+                let step = if menu_input.left { -1 } else { 1 };
+                audio.play_with_settings(game_audio.menu_move.clone(), settings.sfx());
+                menu_events.send(MenuEvent::Adjust { item, step });
+            }
+        }
+        if menu_input.confirm {
+            if let Some(item) = focused {
+                audio.play_with_settings(game_audio.menu_confirm.clone(), settings.sfx());
+                menu_events.send(MenuEvent::Confirm { item });
+            }
+        } else if menu_input.back {
+            audio.play_with_settings(game_audio.menu_back.clone(), settings.sfx());
+            menu_events.send(MenuEvent::Back);
+        }
+    }
+}
+
+fn menu_focus_system(
+    menu_query: Query<&Menu>,
+    mut item_query: Query<(&MenuItem, &Parent, &mut Text)>,
+) {
+    for (item, parent, mut text) in item_query.iter_mut() {
+        let Ok(menu) = menu_query.get(parent.get()) else {
+            continue;
+        };
+        let focused = menu.focused == item.index;
+        let section = &mut text.sections[0];
+        let (value, color) = if focused {
+            (format!("> {} <", item.label), FOCUS_COLOR)
+        } else {
+            (item.label.clone(), ITEM_COLOR)
+        };
+        if section.value != value {
+            section.value = value;
+            section.style.color = color;
+        }
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{AppState, PARTICLE_EFFECTS};
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::ecs::schedule::common_conditions::any_with_component;
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::texture::DEFAULT_IMAGE_HANDLE;
+use bevy::utils::{BoxedFuture, HashMap};
+use rand::{thread_rng, Rng};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// copy of the shipped effects, used when the file can't be loaded or lacks an effect
+const BUILTIN_EFFECTS: &str = include_str!("../../assets/game.particles.ron");
+/// particles alive at once, bursts beyond are cut short
+const PARTICLES_MAX: usize = 2048;
+const PARTICLE_Z: f32 = 6.;
+
+/// CPU particles. They are simulated in `ParticlePool`, apart from the ECS, and
+/// drawn by a pool of sprite entities that are reused from frame to frame.
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<ParticleEffects>()
+            .init_asset_loader::<ParticleEffectsLoader>()
+            .insert_resource(ParticlePool::new(PARTICLES_MAX))
+            .init_resource::<ParticleSprites>()
+            .add_startup_system(particle_setup_system)
+            .add_system(particle_library_system)
+            .add_systems(
+                (
+                    particle_emitter_system,
+                    particle_step_system,
+                    // nothing to draw on without a window
+                    particle_render_system.run_if(any_with_component::<Window>()),
+                )
+                    .chain()
+                    .in_set(OnUpdate(AppState::InGame)),
+            )
+            .add_system(particle_cleanup_system.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+/// Every particle effect of the game by name, see `assets/game.particles.ron`.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "8b1e2c4a-6f3d-4e7b-9c05-d2a4f61e3b97"]
+pub struct ParticleEffects {
+    pub effects: HashMap<String, ParticleEffect>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ParticleEffect {
+    /// particles per second of a `ParticleEmitter`
+    #[serde(default)]
+    pub rate: f32,
+    /// particles of a burst of scale 1
+    #[serde(default)]
+    pub burst: usize,
+    /// min and max seconds a particle lives
+    pub lifetime: (f32, f32),
+    /// min and max starting speed, in world units per second
+    pub speed: (f32, f32),
+    /// degrees, 0 is right and 90 up
+    #[serde(default)]
+    pub direction: f32,
+    /// degrees of the cone particles start in, around `direction`
+    #[serde(default = "full_circle")]
+    pub spread: f32,
+    /// world units per second, per second
+    #[serde(default)]
+    pub gravity: (f32, f32),
+    /// (r, g, b, a) over the life of a particle, evenly spaced
+    pub colors: Vec<(f32, f32, f32, f32)>,
+    /// world units over the life of a particle, evenly spaced
+    pub sizes: Vec<f32>,
+    /// image path, plain squares without one
+    #[serde(default)]
+    pub sprite: Option<String>,
+    #[serde(skip)]
+    texture: Handle<Image>,
+}
+
+fn full_circle() -> f32 {
+    360.
+}
+
+impl ParticleEffect {
+    fn is_valid(&self) -> bool {
+        !self.colors.is_empty()
+            && !self.sizes.is_empty()
+            && 0. < self.lifetime.0
+            && self.lifetime.0 <= self.lifetime.1
+            && self.speed.0 <= self.speed.1
+    }
+}
+
+/// Effects ready to play: the loaded file over the builtin one.
+#[derive(Resource)]
+pub struct ParticleLibrary(HashMap<String, Arc<ParticleEffect>>);
+
+#[derive(Resource)]
+struct ParticleEffectsHandle(Handle<ParticleEffects>);
+
+impl ParticleLibrary {
+    fn new(effects: Option<&ParticleEffects>, asset_server: &AssetServer) -> Self {
+        let builtin: ParticleEffects =
+            ron::from_str(BUILTIN_EFFECTS).expect("the builtin particle effects are valid");
+        let mut library = builtin.effects;
+        for (name, effect) in effects.into_iter().flat_map(|effects| &effects.effects) {
+            if effect.is_valid() {
+                library.insert(name.clone(), effect.clone());
+            } else {
+                warn!("{PARTICLE_EFFECTS}: invalid effect `{name}`");
+            }
+        }
+
+        let library = library
+            .into_iter()
+            .map(|(name, mut effect)| {
+                effect.texture = match &effect.sprite {
+                    Some(path) => asset_server.load(path.as_str()),
+                    None => DEFAULT_IMAGE_HANDLE.typed(),
+                };
+                (name, Arc::new(effect))
+            })
+            .collect();
+        Self(library)
+    }
+}
+
+/// Spawns particles of its effect at the `rate` of the effect, where it stands.
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub effect: String,
+    /// part of a particle left over from the last frames
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: &str) -> Self {
+        Self {
+            effect: effect.into(),
+            pending: 0.,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+    effect: Arc<ParticleEffect>,
+}
+
+impl Particle {
+    pub fn color(&self) -> Color {
+        let (r, g, b, a) = sample(&self.effect.colors, self.age / self.lifetime);
+        Color::rgba(r, g, b, a)
+    }
+
+    pub fn size(&self) -> f32 {
+        sample(&self.effect.sizes, self.age / self.lifetime)
+    }
+}
+
+/// Value at `t`, from 0 to 1, of `keys` evenly spaced and linearly interpolated.
+fn sample<T: Copy + Lerp>(keys: &[T], t: f32) -> T {
+    let position = t.clamp(0., 1.) * (keys.len() - 1) as f32;
+    let i = (position as usize).min(keys.len() - 1);
+    match keys.get(i + 1) {
+        Some(next) => keys[i].lerp(*next, position - i as f32),
+        None => keys[i],
+    }
+}
+
+trait Lerp {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for (f32, f32, f32, f32) {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        (
+            self.0.lerp(other.0, t),
+            self.1.lerp(other.1, t),
+            self.2.lerp(other.2, t),
+            self.3.lerp(other.3, t),
+        )
+    }
+}
+
+/// Live particles. The pool never holds more than `max`, the room it allocates
+/// up front, so it never reallocates; particles that don't fit are dropped.
+#[derive(Resource)]
+pub struct ParticlePool {
+    particles: Vec<Particle>,
+    /// particles alive at once
+    max: usize,
+}
+
+impl ParticlePool {
+    pub fn new(max: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(max),
+            max,
+        }
+    }
+
+    /// Spawn `count` particles of `effect` at `position`, `speed_scale` times as fast.
+    pub fn emit(
+        &mut self,
+        effect: &Arc<ParticleEffect>,
+        position: Vec2,
+        count: usize,
+        speed_scale: f32,
+        rng: &mut impl Rng,
+    ) {
+        let room = self.max - self.particles.len();
+        for _ in 0..count.min(room) {
+            let angle = effect.direction + rng.gen_range(-0.5..=0.5) * effect.spread;
+            let speed = rng.gen_range(effect.speed.0..=effect.speed.1) * speed_scale;
+            self.particles.push(Particle {
+                position,
+                velocity: Vec2::from_angle(angle.to_radians()) * speed,
+                age: 0.,
+                lifetime: rng.gen_range(effect.lifetime.0..=effect.lifetime.1),
+                effect: effect.clone(),
+            });
+        }
+    }
+
+    /// A burst of `effect`, `scale` times as wide: it has `scale²` as many
+    /// particles, `scale` times as fast.
+    pub fn burst(
+        &mut self,
+        effect: &Arc<ParticleEffect>,
+        position: Vec2,
+        scale: f32,
+        rng: &mut impl Rng,
+    ) {
+        let count = (effect.burst as f32 * scale * scale).round() as usize;
+        self.emit(effect, position, count, scale, rng);
+    }
+
+    /// Move the particles `delta` seconds on, dropping the ones that are over.
+    pub fn step(&mut self, delta: f32) {
+        self.particles.retain_mut(|particle| {
+            particle.age += delta;
+            if particle.age >= particle.lifetime {
+                return false;
+            }
+            let gravity = Vec2::new(particle.effect.gravity.0, particle.effect.gravity.1);
+            particle.velocity += gravity * delta;
+            particle.position += particle.velocity * delta;
+            true
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
+
+/// Particle bursts for any system.
+#[derive(SystemParam)]
+pub struct Particles<'w> {
+    pool: ResMut<'w, ParticlePool>,
+    library: Res<'w, ParticleLibrary>,
+}
+
+impl Particles<'_> {
+    /// See `ParticlePool::burst`.
+    pub fn burst(&mut self, effect: &str, position: Vec2, scale: f32) {
+        let Some(effect) = self.library.0.get(effect) else {
+            warn!("No particle effect `{effect}`");
+            return;
+        };
+        self.pool.burst(effect, position, scale, &mut thread_rng());
+    }
+}
+
+/// Sprites drawing the particles, reused from frame to frame.
+#[derive(Resource, Default)]
+struct ParticleSprites(Vec<Entity>);
+
+#[derive(Component)]
+struct ParticleSprite;
+
+fn particle_setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ParticleLibrary::new(None, &asset_server));
+    commands.insert_resource(ParticleEffectsHandle(asset_server.load(PARTICLE_EFFECTS)));
+}
+
+/// Rebuild the library whenever the effects file is loaded or changes.
+fn particle_library_system(
+    mut events: EventReader<AssetEvent<ParticleEffects>>,
+    asset_server: Res<AssetServer>,
+    handle: Res<ParticleEffectsHandle>,
+    effects: Res<Assets<ParticleEffects>>,
+    mut library: ResMut<ParticleLibrary>,
+) {
+    for event in events.iter() {
+        if let AssetEvent::Created { handle: loaded } | AssetEvent::Modified { handle: loaded } =
+            event
+        {
+            if *loaded == handle.0 {
+                *library = ParticleLibrary::new(effects.get(loaded), &asset_server);
+            }
+        }
+    }
+}
+
+fn particle_emitter_system(
+    time: Res<Time>,
+    library: Res<ParticleLibrary>,
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(&GlobalTransform, &mut ParticleEmitter)>,
+) {
+    let mut rng = thread_rng();
+    for (transform, mut emitter) in query.iter_mut() {
+        let Some(effect) = library.0.get(&emitter.effect) else {
+            continue;
+        };
+        emitter.pending += effect.rate * time.delta_seconds();
+        let count = emitter.pending as usize;
+        emitter.pending -= count as f32;
+        pool.emit(effect, transform.translation().xy(), count, 1., &mut rng);
+    }
+}
+
+fn particle_step_system(time: Res<Time>, mut pool: ResMut<ParticlePool>) {
+    pool.step(time.delta_seconds());
+}
+
+/// Show one sprite per particle, hide the sprites left over and spawn more when
+/// there are not enough.
+fn particle_render_system(
+    mut commands: Commands,
+    pool: Res<ParticlePool>,
+    mut sprites: ResMut<ParticleSprites>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &mut Sprite,
+            &mut Handle<Image>,
+            &mut Visibility,
+        ),
+        With<ParticleSprite>,
+    >,
+) {
+    let mut particles = pool.iter();
+    for entity in sprites.0.iter() {
+        // spawned last frame and not there yet
+        let Ok((mut transform, mut sprite, mut texture, mut visibility)) = query.get_mut(*entity)
+        else {
+            continue;
+        };
+        let Some(particle) = particles.next() else {
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+            continue;
+        };
+        transform.translation = particle.position.extend(PARTICLE_Z);
+        sprite.color = particle.color();
+        sprite.custom_size = Some(Vec2::splat(particle.size()));
+        if *texture != particle.effect.texture {
+            *texture = particle.effect.texture.clone();
+        }
+        *visibility = Visibility::Inherited;
+    }
+
+    for particle in particles {
+        let entity = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: particle.color(),
+                        custom_size: Some(Vec2::splat(particle.size())),
+                        ..default()
+                    },
+                    texture: particle.effect.texture.clone(),
+                    transform: Transform::from_translation(particle.position.extend(PARTICLE_Z)),
+                    ..default()
+                },
+                ParticleSprite,
+                Name::new("Particle"),
+            ))
+            .id();
+        sprites.0.push(entity);
+    }
+}
+
+/// The run cleanup despawns the sprites, forget them along with the particles.
+fn particle_cleanup_system(mut pool: ResMut<ParticlePool>, mut sprites: ResMut<ParticleSprites>) {
+    pool.clear();
+    sprites.0.clear();
+}
+
+#[derive(Default)]
+pub struct ParticleEffectsLoader;
+
+impl AssetLoader for ParticleEffectsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let effects = ron::de::from_bytes::<ParticleEffects>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(effects));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["particles.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn effect(burst: usize, lifetime: f32, gravity: (f32, f32)) -> Arc<ParticleEffect> {
+        Arc::new(ParticleEffect {
+            rate: 0.,
+            burst,
+            lifetime: (lifetime, lifetime),
+            speed: (0., 0.),
+            direction: 0.,
+            spread: 360.,
+            gravity,
+            colors: vec![(1., 1., 1., 1.)],
+            sizes: vec![1.],
+            sprite: None,
+            texture: default(),
+        })
+    }
+
+    #[test]
+    fn emit_stops_at_max() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut pool = ParticlePool::new(10);
+        let effect = effect(0, 1., (0., 0.));
+        pool.emit(&effect, Vec2::ZERO, 6, 1., &mut rng);
+        assert_eq!(pool.iter().count(), 6);
+        pool.emit(&effect, Vec2::ZERO, 25, 1., &mut rng);
+        assert_eq!(pool.iter().count(), 10);
+    }
+
+    #[test]
+    fn step_drops_expired_particles() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut pool = ParticlePool::new(100);
+        pool.emit(&effect(0, 0.5, (0., 0.)), Vec2::ZERO, 3, 1., &mut rng);
+        pool.emit(&effect(0, 2., (0., 0.)), Vec2::ZERO, 4, 1., &mut rng);
+        pool.step(1.);
+        assert_eq!(pool.iter().count(), 4);
+        pool.step(1.);
+        assert_eq!(pool.iter().count(), 0);
+    }
+
+    #[test]
+    fn step_applies_gravity() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut pool = ParticlePool::new(1);
+        // no starting speed, only gravity moves it
+        pool.emit(&effect(0, 10., (0., -10.)), Vec2::ZERO, 1, 1., &mut rng);
+        pool.step(0.5);
+        pool.step(0.5);
+        let particle = pool.iter().next().unwrap();
+        assert_eq!(particle.velocity, Vec2::new(0., -10.));
+        // velocity is updated before position
+        assert_eq!(particle.position, Vec2::new(0., -7.5));
+    }
+
+    #[test]
+    fn burst_grows_with_scale_squared() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut pool = ParticlePool::new(1000);
+        let effect = effect(10, 1., (0., 0.));
+        pool.burst(&effect, Vec2::ZERO, 1., &mut rng);
+        assert_eq!(pool.iter().count(), 10);
+        pool.clear();
+        pool.burst(&effect, Vec2::ZERO, 2., &mut rng);
+        assert_eq!(pool.iter().count(), 40);
+        pool.clear();
+        // 10 * 0.5² = 2.5, rounded
+        pool.burst(&effect, Vec2::ZERO, 0.5, &mut rng);
+        assert_eq!(pool.iter().count(), 3);
+    }
+
+    #[test]
+    fn sample_interpolates_between_keys() {
+        let keys = [0., 10., 30.];
+        assert_eq!(sample(&keys, 0.), 0.);
+        assert_eq!(sample(&keys, 0.25), 5.);
+        assert_eq!(sample(&keys, 0.5), 10.);
+        assert_eq!(sample(&keys, 0.75), 20.);
+        assert_eq!(sample(&keys, 1.), 30.);
+    }
+
+    #[test]
+    fn sample_single_key() {
+        assert_eq!(sample(&[4.], 0.), 4.);
+        assert_eq!(sample(&[4.], 0.7), 4.);
+        assert_eq!(sample(&[4.], 1.), 4.);
+    }
+
+    #[test]
+    fn sample_clamps_t() {
+        let keys = [(0., 0., 0., 0.), (1., 1., 1., 1.)];
+        assert_eq!(sample(&keys, -1.), keys[0]);
+        assert_eq!(sample(&keys, 2.), keys[1]);
+    }
+}
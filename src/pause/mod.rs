@@ -0,0 +1,196 @@
+use crate::menu::{spawn_menu, MenuEvent};
+use crate::settings::{spawn_options_menu, OptionEntry, Settings};
+use crate::{AppState, GameFont};
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<PauseState>()
+            .insert_resource(PausePage::default())
+            // every gameplay system lives in this set, pausing freezes them all
+            .configure_set(OnUpdate(AppState::InGame).run_if(in_state(PauseState::Running)))
+            .add_system(pause_input_system.run_if(in_state(AppState::InGame)))
+            .add_system(pause_enter_system.in_schedule(OnEnter(PauseState::Paused)))
+            .add_system(pause_exit_system.in_schedule(OnExit(PauseState::Paused)))
+            .add_systems(
+                (pause_page_system, pause_menu_system).in_set(OnUpdate(PauseState::Paused)),
+            );
+    }
+}
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum PausePage {
+    #[default]
+    Main,
+    Options,
+}
+
+#[derive(Component, Clone, Copy)]
+enum PauseAction {
+    Resume,
+    Restart,
+    Options,
+    QuitToTitle,
+}
+
+#[derive(Component)]
+struct PauseScreen;
+
+/// The pause key toggles the pause, losing the window focus pauses.
+fn pause_input_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut focused: EventReader<WindowFocused>,
+    pause_state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    let lost_focus = focused.iter().any(|event| !event.focused);
+    let start = gamepads.iter().any(|gamepad| {
+        gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))
+    });
+    match pause_state.0 {
+        PauseState::Running => {
+            if lost_focus || start || keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::P])
+            {
+                next_state.set(PauseState::Paused);
+            }
+        }
+        // escape goes through the menu, as back
+        PauseState::Paused => {
+            if keyboard_input.just_pressed(KeyCode::P) {
+                next_state.set(PauseState::Running);
+            }
+        }
+    }
+}
+
+fn pause_enter_system(mut time: ResMut<Time>, mut page: ResMut<PausePage>) {
+    time.pause();
+    *page = PausePage::Main;
+    page.set_changed();
+}
+
+fn pause_exit_system(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    query: Query<Entity, With<PauseScreen>>,
+) {
+    time.unpause();
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_page_system(
+    mut commands: Commands,
+    page: Res<PausePage>,
+    game_font: Res<GameFont>,
+    settings: Res<Settings>,
+    query: Query<Entity, With<PauseScreen>>,
+) {
+    if !page.is_changed() {
+        return;
+    }
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let tr = |english| settings.language.tr(english);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    gap: Size::all(Val::Px(32.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseScreen,
+            Name::new("PauseScreen"),
+        ))
+        .with_children(|parent| {
+            let title = match *page {
+                PausePage::Main => tr("PAUSED"),
+                PausePage::Options => tr("OPTIONS"),
+            };
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: game_font.0.clone(),
+                    font_size: 28.,
+                    color: Color::WHITE,
+                },
+            ));
+            match *page {
+                PausePage::Main => {
+                    spawn_menu(
+                        parent,
+                        &game_font.0,
+                        vec![
+                            (tr("RESUME"), PauseAction::Resume),
+                            (tr("RESTART"), PauseAction::Restart),
+                            (tr("OPTIONS"), PauseAction::Options),
+                            (tr("QUIT TO TITLE"), PauseAction::QuitToTitle),
+                        ],
+                    );
+                }
+                PausePage::Options => spawn_options_menu(parent, &game_font.0, &settings),
+            }
+        });
+}
+
+fn pause_menu_system(
+    mut menu_events: EventReader<MenuEvent>,
+    mut page: ResMut<PausePage>,
+    mut pause_state: ResMut<NextState<PauseState>>,
+    mut app_state: ResMut<NextState<AppState>>,
+    action_query: Query<&PauseAction>,
+    option_query: Query<&OptionEntry>,
+) {
+    for event in menu_events.iter() {
+        let action = match event {
+            MenuEvent::Confirm { item } if option_query.get(*item) == Ok(&OptionEntry::Back) => {
+                *page = PausePage::Main;
+                continue;
+            }
+            MenuEvent::Confirm { item } => action_query.get(*item).ok().copied(),
+            MenuEvent::Adjust { .. } => None,
+            MenuEvent::Back if *page == PausePage::Options => {
+                *page = PausePage::Main;
+                continue;
+            }
+            MenuEvent::Back => Some(PauseAction::Resume),
+        };
+        match action {
+            Some(PauseAction::Resume) => pause_state.set(PauseState::Running),
+            Some(PauseAction::Restart) => {
+                pause_state.set(PauseState::Running);
+                // entering the state it is already in replays its exit and enter
+                app_state.set(AppState::InGame);
+            }
+            Some(PauseAction::Options) => *page = PausePage::Options,
+            Some(PauseAction::QuitToTitle) => {
+                pause_state.set(PauseState::Running);
+                app_state.set(AppState::Title);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::components::{Player, Velocity};
+use crate::PlayerState;
+use bevy::prelude::*;
+
+const DASH_KEY: KeyCode = KeyCode::LShift;
+/// seconds the burst lasts, the ship can't be hit meanwhile
+const DASH_TIME: f32 = 0.18;
+const DASH_SPEED: f32 = 8.;
+/// seconds between two dashes
+const DASH_COOLDOWN: f64 = 1.2;
+/// seconds an afterimage takes to fade out
+const AFTERIMAGE_TIME: f32 = 0.25;
+
+/// Sideways burst in progress, `direction` is -1 (left) or 1 (right).
+#[derive(Component)]
+pub struct Dashing {
+    direction: f32,
+    timer: Timer,
+}
+
+#[derive(Component)]
+pub struct Afterimage(Timer);
+
+#[derive(Component)]
+pub struct DashMeterFill;
+
+pub fn spawn_dash_meter(parent: &mut ChildBuilder) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(80.), Val::Px(4.)),
+                    ..default()
+                },
+                background_color: Color::rgba(1., 1., 1., 0.15).into(),
+                ..default()
+            },
+            Name::new("DashMeter"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                DashMeterFill,
+            ));
+        });
+}
+
+pub fn player_dash_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut player_state: ResMut<PlayerState>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Velocity,
+            &Transform,
+            &Handle<Image>,
+            Option<&mut Dashing>,
+        ),
+        With<Player>,
+    >,
+) {
+    let Ok((entity, mut velocity, transform, texture, dashing)) = player_query.get_single_mut()
+    else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+
+    let Some(mut dashing) = dashing else {
+        if keyboard_input.just_pressed(DASH_KEY) && now - player_state.last_dash >= DASH_COOLDOWN {
+            // dash the way the ship is going, right when it stands still
+            let direction = if velocity.x < 0. { -1. } else { 1. };
+            commands.entity(entity).insert(Dashing {
+                direction,
+                timer: Timer::from_seconds(DASH_TIME, TimerMode::Once),
+            });
+            player_state.last_dash = now;
+            player_state.invulnerable_until =
+                player_state.invulnerable_until.max(now + DASH_TIME as f64);
+        }
+        return;
+    };
+
+    if dashing.timer.tick(time.delta()).finished() {
+        commands.entity(entity).remove::<Dashing>();
+        velocity.x = 0.;
+        return;
+    }
+    velocity.x = dashing.direction * DASH_SPEED;
+    velocity.y = 0.;
+
+    commands.spawn((
+        SpriteBundle {
+            texture: texture.clone(),
+            sprite: Sprite {
+                color: Color::rgba(0.5, 0.8, 1., 0.5),
+                ..default()
+            },
+            transform: Transform {
+                translation: transform.translation.truncate().extend(9.),
+                ..*transform
+            },
+            ..default()
+        },
+        Afterimage(Timer::from_seconds(AFTERIMAGE_TIME, TimerMode::Once)),
+    ));
+}
+
+pub fn afterimage_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Sprite, &mut Afterimage)>,
+) {
+    for (entity, mut sprite, mut afterimage) in query.iter_mut() {
+        if afterimage.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color.set_a(0.5 * afterimage.0.percent_left());
+        }
+    }
+}
+
+pub fn dash_meter_system(
+    time: Res<Time>,
+    player_state: Res<PlayerState>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<DashMeterFill>>,
+) {
+    let ready = ((time.elapsed_seconds_f64() - player_state.last_dash) / DASH_COOLDOWN).min(1.);
+    for (mut style, mut color) in fill_query.iter_mut() {
+        style.size.width = Val::Percent(ready as f32 * 100.);
+        *color = if ready >= 1. {
+            Color::rgb(0.3, 0.9, 1.)
+        } else {
+            Color::rgb(0.3, 0.5, 0.7)
+        }
+        .into();
+    }
+}
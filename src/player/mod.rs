@@ -1,24 +1,47 @@
-use crate::components::{FromPlayer, Laser, Movable, Player, SpriteSize, Velocity};
+use crate::animation::thruster::{spawn_thruster, ThrusterTexture};
+use crate::components::{Hitbox, Movable, Player, SpriteSize, Velocity};
+use crate::particles::ParticleEmitter;
+use crate::powerup::{ActivePowerUps, PowerUpKind};
+use crate::ship::SelectedShip;
+use crate::weapon::{Weapon, WeaponMode};
 use crate::{
-    GameTexture, PlayerState, WinSize, BASE_SPEED, PLAYER_LASER_SIZE, PLAYER_RESPAWN_DELAY,
-    PLAYER_SIZE, SPRITE_SCALE, TIME_STEP,
+    movable_system, AppState, FireSettings, GameTexture, MovementRegion, MovementSettings,
+    PlayerState, WinSize, PLAYER_RAPID_FIRE_INTERVAL, PLAYER_RESPAWN_DELAY,
+    PLAYER_RESPAWN_INVULNERABILITY, PLAYER_SPEED_BOOST, PLAYER_SPREAD_ANGLE, SPRITE_SCALE,
 };
 use bevy::prelude::*;
 use bevy::time::common_conditions::on_timer;
+use dash::Dashing;
 use std::time::Duration;
 
+mod dash;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerState::default())
+            .insert_resource(FireSettings::default())
+            .insert_resource(MovementSettings::default())
             //.add_startup_systems((player_spawn_system.in_base_set(StartupSet::PostStartup),))
-            .add_systems((
-                //player_movement_system,
-                player_spawn_system.run_if(on_timer(Duration::from_secs_f32(0.5))),
-                player_keyboard_event_system,
-                player_fire_system,
-            ));
+            .add_systems(
+                (
+                    //player_movement_system,
+                    player_spawn_system.run_if(on_timer(Duration::from_secs_f32(0.5))),
+                    player_keyboard_event_system,
+                    player_bounds_system.after(movable_system),
+                    player_fire_system,
+                    player_heat_system,
+                    heat_meter_system,
+                    player_invulnerability_system,
+                    dash::player_dash_system
+                        .after(player_keyboard_event_system)
+                        .before(movable_system),
+                    dash::afterimage_system,
+                    dash::dash_meter_system,
+                )
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
     }
 }
 
@@ -27,92 +50,281 @@ fn player_spawn_system(
     mut player_state: ResMut<PlayerState>,
     time: Res<Time>,
     game_texture: Res<GameTexture>,
+    thruster: Res<ThrusterTexture>,
+    selected_ship: Res<SelectedShip>,
     win_size: Res<WinSize>,
 ) {
     let now = time.elapsed_seconds_f64();
-    let last_shot = player_state.last_shot;
+    let died_at = player_state.died_at;
 
-    if !player_state.on && (last_shot == -1. || now > last_shot + PLAYER_RESPAWN_DELAY) {
+    if !player_state.on
+        && player_state.lives > 0
+        && (died_at == -1. || now > died_at + PLAYER_RESPAWN_DELAY)
+    {
+        let ship = &game_texture.ships[selected_ship.0];
         let bottom = -win_size.height / 2.0;
-        commands.spawn((
-            SpriteBundle {
-                texture: game_texture.player.clone(),
-                transform: Transform {
-                    translation: Vec3::new(
-                        0.,
-                        bottom + PLAYER_SIZE.1 / 2. * SPRITE_SCALE.1 + 5.,
-                        10.,
-                    ),
-                    scale: Vec3::new(SPRITE_SCALE.0, SPRITE_SCALE.1, 1.),
+        commands
+            .spawn((
+                SpriteBundle {
+                    texture: ship.image.clone(),
+                    transform: Transform {
+                        translation: Vec3::new(
+                            0.,
+                            bottom + ship.size.y / 2. * SPRITE_SCALE.1 + 5.,
+                            10.,
+                        ),
+                        scale: Vec3::new(SPRITE_SCALE.0, SPRITE_SCALE.1, 1.),
+                        ..default()
+                    },
                     ..default()
                 },
-                ..default()
-            },
-            Player,
-            Movable {
-                auto_despawn: false,
-            },
-            Velocity { x: 0.0, y: 0.0 },
-            SpriteSize::from(PLAYER_SIZE),
-            Name::new("player"),
-        ));
+                Player,
+                Movable {
+                    auto_despawn: false,
+                },
+                Velocity { x: 0.0, y: 0.0 },
+                SpriteSize(ship.size),
+                Hitbox(ship.hitbox),
+                Weapon::player(&selected_ship.def().loadout),
+                Name::new("player"),
+            ))
+            .with_children(|parent| {
+                let nozzle = Vec3::new(0., -ship.size.y / 2. + 6., -1.);
+                spawn_thruster(parent, &thruster, "thrust", nozzle, 1.5, false);
+                parent.spawn((
+                    TransformBundle::from_transform(Transform::from_translation(nozzle)),
+                    ParticleEmitter::new("exhaust"),
+                ));
+            });
+        if died_at != -1. {
+            player_state.invulnerable_until = now + PLAYER_RESPAWN_INVULNERABILITY;
+        }
         player_state.spawned();
     }
 }
 
 fn player_fire_system(
-    mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
-    game_texture: Res<GameTexture>,
-    query: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+    settings: Res<FireSettings>,
+    active_powerups: Res<ActivePowerUps>,
+    selected_ship: Res<SelectedShip>,
+    mut player_state: ResMut<PlayerState>,
+    mut query: Query<&mut Weapon, With<Player>>,
 ) {
-    if let Ok(player_transform) = query.get_single() {
-        if keyboard_input.just_pressed(KeyCode::Space) {
-            let (x, y) = (
-                player_transform.translation.x,
-                player_transform.translation.y,
-            );
-            let x_offset = PLAYER_SIZE.0 / 2.0 * SPRITE_SCALE.0 - 5.0;
-
-            let mut spawn_laser = |x_offset| {
-                commands.spawn((
-                    SpriteBundle {
-                        texture: game_texture.player_laser.clone(),
-                        transform: Transform {
-                            translation: Vec3::new(x + x_offset, y, 0.),
-                            scale: Vec3::new(SPRITE_SCALE.0, SPRITE_SCALE.1, 1.),
-                            ..default()
-                        },
+    if player_state.weapon_mode != WeaponMode::Laser {
+        return;
+    }
+    if let Ok(mut weapon) = query.get_single_mut() {
+        let loadout = &selected_ship.def().loadout;
+        if active_powerups.is_active(PowerUpKind::SpreadShot) {
+            weapon.ways = loadout.ways + 2;
+            weapon.spread = loadout.spread + 2. * PLAYER_SPREAD_ANGLE;
+        } else {
+            weapon.ways = loadout.ways;
+            weapon.spread = loadout.spread;
+        }
+
+        // a tap fires after `min_interval`, holding the key autofires
+        // (rapid fire autofires faster, even with autofire turned off)
+        let rapid = active_powerups.is_active(PowerUpKind::RapidFire);
+        let interval = if keyboard_input.just_pressed(KeyCode::Space) {
+            Some(settings.min_interval)
+        } else if keyboard_input.pressed(KeyCode::Space) && rapid {
+            Some(PLAYER_RAPID_FIRE_INTERVAL.max(settings.min_interval))
+        } else if keyboard_input.pressed(KeyCode::Space) && settings.autofire {
+            Some(settings.autofire_interval.max(settings.min_interval))
+        } else {
+            None
+        };
+
+        let now = time.elapsed_seconds_f64();
+        let Some(interval) = interval else {
+            return;
+        };
+        if player_state.overheated || now - player_state.last_fire < interval {
+            return;
+        }
+
+        weapon.trigger = true;
+        player_state.last_fire = now;
+        if settings.overheat {
+            player_state.heat = (player_state.heat + settings.heat_per_shot).min(1.);
+            player_state.overheated = player_state.heat >= 1.;
+        }
+    }
+}
+
+fn player_heat_system(
+    time: Res<Time>,
+    settings: Res<FireSettings>,
+    mut player_state: ResMut<PlayerState>,
+) {
+    if !settings.overheat {
+        player_state.heat = 0.;
+        player_state.overheated = false;
+        return;
+    }
+    let heat = (player_state.heat - settings.cooling_rate * time.delta_seconds()).max(0.);
+    player_state.heat = heat;
+    if player_state.overheated && heat <= settings.overheat_recovery {
+        player_state.overheated = false;
+    }
+}
+
+#[derive(Component)]
+struct HeatMeter;
+
+#[derive(Component)]
+struct HeatMeterFill;
+
+/// Spawn the dash meter over the heat meter under `parent`, a HUD node.
+pub fn spawn_meters(parent: &mut ChildBuilder) {
+    dash::spawn_dash_meter(parent);
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(80.), Val::Px(6.)),
+                    ..default()
+                },
+                background_color: Color::rgba(1., 1., 1., 0.15).into(),
+                ..default()
+            },
+            HeatMeter,
+            Name::new("HeatMeter"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(0.), Val::Percent(100.)),
                         ..default()
                     },
-                    Laser,
-                    Velocity { x: 0.0, y: 1.0 },
-                    Movable { auto_despawn: true },
-                    FromPlayer,
-                    SpriteSize::from(PLAYER_LASER_SIZE),
-                    Name::new("player_laser"),
-                ));
-            };
+                    ..default()
+                },
+                HeatMeterFill,
+            ));
+        });
+}
 
-            spawn_laser(x_offset);
-            spawn_laser(-x_offset);
+fn heat_meter_system(
+    settings: Res<FireSettings>,
+    player_state: Res<PlayerState>,
+    mut meter_query: Query<&mut Visibility, With<HeatMeter>>,
+    mut fill_query: Query<(&mut Style, &mut BackgroundColor), With<HeatMeterFill>>,
+) {
+    for mut visibility in meter_query.iter_mut() {
+        *visibility = if settings.overheat {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for (mut style, mut color) in fill_query.iter_mut() {
+        style.size.width = Val::Percent(player_state.heat * 100.);
+        *color = if player_state.overheated {
+            Color::rgb(1., 0.2, 0.2)
+        } else {
+            Color::rgb(1., 0.7, 0.2)
         }
+        .into();
+    }
+}
+
+/// Blink the ship while enemy lasers go through it.
+fn player_invulnerability_system(
+    time: Res<Time>,
+    player_state: Res<PlayerState>,
+    mut query: Query<(&mut Visibility, Option<&Dashing>), With<Player>>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for (mut visibility, dashing) in query.iter_mut() {
+        // a dash shows its afterimages instead
+        *visibility = if player_state.is_invulnerable(now)
+            && dashing.is_none()
+            && ((now * 10.) as u64).is_multiple_of(2)
+        {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
     }
 }
 
 fn player_keyboard_event_system(
     keyboard_input: Res<Input<KeyCode>>,
-    mut player_query: Query<&mut Velocity, With<Player>>,
+    time: Res<Time>,
+    settings: Res<MovementSettings>,
+    active_powerups: Res<ActivePowerUps>,
+    selected_ship: Res<SelectedShip>,
+    mut player_query: Query<&mut Velocity, (With<Player>, Without<Dashing>)>,
 ) {
     if let Ok(mut velocity) = player_query.get_single_mut() {
-        velocity.x = if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left)
-        {
-            -1.
-        } else if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
-            1.
+        let speed = selected_ship.def().speed
+            * if active_powerups.is_active(PowerUpKind::SpeedBoost) {
+                PLAYER_SPEED_BOOST
+            } else {
+                1.
+            };
+        let pressed = |keys: [KeyCode; 2]| keys.iter().any(|key| keyboard_input.pressed(*key));
+        let axis = |negative, positive| match (pressed(negative), pressed(positive)) {
+            (true, false) => -1.,
+            (false, true) => 1.,
+            _ => 0.,
+        };
+        let input = Vec2::new(
+            axis([KeyCode::A, KeyCode::Left], [KeyCode::D, KeyCode::Right]),
+            axis([KeyCode::S, KeyCode::Down], [KeyCode::W, KeyCode::Up]),
+        )
+        .normalize_or_zero();
+
+        let current = Vec2::new(velocity.x, velocity.y);
+        let new = if settings.acceleration <= 0. {
+            input * speed
         } else {
-            0.
+            let dt = time.delta_seconds();
+            let drag = (1. - settings.drag * dt).max(0.);
+            let mut new = current + input * settings.acceleration * dt;
+            // only released axes slow down, held ones keep their momentum
+            if input.x == 0. {
+                new.x *= drag;
+            }
+            if input.y == 0. {
+                new.y *= drag;
+            }
+            new.clamp_length_max(speed)
         };
+        velocity.x = new.x;
+        velocity.y = new.y;
+    }
+}
+
+/// Keep the ship inside its `MovementRegion`, after `movable_system` moved it.
+fn player_bounds_system(
+    win_size: Res<WinSize>,
+    settings: Res<MovementSettings>,
+    mut player_query: Query<(&mut Transform, &mut Velocity, &SpriteSize), With<Player>>,
+) {
+    for (mut transform, mut velocity, size) in player_query.iter_mut() {
+        let half = size.0 * transform.scale.truncate() / 2.;
+        let (left, bottom) = (-win_size.width / 2., -win_size.height / 2.);
+        let top = match settings.region {
+            MovementRegion::BottomBand(fraction) => bottom + win_size.height * fraction,
+            MovementRegion::FullScreen => win_size.height / 2.,
+        };
+        let min = Vec2::new(left + half.x, bottom + half.y);
+        let max = Vec2::new(-left - half.x, (top - half.y).max(min.y));
+
+        let position = transform.translation.truncate();
+        let clamped = position.clamp(min, max);
+        if clamped.x != position.x {
+            velocity.x = 0.;
+        }
+        if clamped.y != position.y {
+            velocity.y = 0.;
+        }
+        transform.translation = clamped.extend(transform.translation.z);
     }
 }
 
@@ -0,0 +1,350 @@
+use crate::animation::{strip, Animation, AnimationClip, AnimationFinished, LoopMode, OnFinish};
+use crate::components::{Hitbox, Movable, Player, PowerUpToSpawn, SpriteSize, Velocity};
+use crate::{
+    AppState, GameAtlas, GameFont, PlayerState, PLAYER_BOMBS_MAX, PLAYER_LIVES_MAX,
+    POWERUP_DURATION, POWERUP_SIZE,
+};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use bevy::utils::HashMap;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// a timed power-up never stacks beyond this many durations
+const POWERUP_STACK_MAX: f32 = 2.;
+const SHIELD_CHARGES_MAX: u32 = 3;
+const POWERUP_FALL_SPEED: f32 = 0.8;
+const POWERUP_GROW_FRAMES: u32 = 4;
+const POWERUP_PULSE_FRAMES: u32 = 4;
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ActivePowerUps::default())
+            .add_startup_system(powerup_setup_system)
+            .add_systems(
+                (
+                    powerup_to_spawn_system,
+                    powerup_appeared_system,
+                    player_pickup_powerup_system,
+                    powerup_timer_system,
+                    player_shield_visual_system,
+                )
+                    .in_set(OnUpdate(AppState::InGame)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PowerUpKind {
+    SpreadShot,
+    RapidFire,
+    Shield,
+    ExtraLife,
+    SpeedBoost,
+    Bomb,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 6] = [
+        PowerUpKind::SpreadShot,
+        PowerUpKind::RapidFire,
+        PowerUpKind::Shield,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::SpeedBoost,
+        PowerUpKind::Bomb,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PowerUpKind::SpreadShot => "S",
+            PowerUpKind::RapidFire => "R",
+            PowerUpKind::Shield => "H",
+            PowerUpKind::ExtraLife => "1",
+            PowerUpKind::SpeedBoost => "V",
+            PowerUpKind::Bomb => "B",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::SpreadShot => "SPREAD",
+            PowerUpKind::RapidFire => "RAPID",
+            PowerUpKind::Shield => "SHIELD",
+            PowerUpKind::ExtraLife => "1UP",
+            PowerUpKind::SpeedBoost => "SPEED",
+            PowerUpKind::Bomb => "BOMB",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            PowerUpKind::SpreadShot => Color::rgb(1., 0.6, 0.2),
+            PowerUpKind::RapidFire => Color::rgb(1., 0.2, 0.2),
+            PowerUpKind::Shield => Color::rgb(0.3, 0.8, 1.),
+            PowerUpKind::ExtraLife => Color::rgb(0.3, 1., 0.3),
+            PowerUpKind::SpeedBoost => Color::rgb(1., 1., 0.3),
+            PowerUpKind::Bomb => Color::rgb(0.8, 0.4, 1.),
+        }
+    }
+}
+
+/// A pickup falling down the screen.
+#[derive(Component)]
+pub struct PowerUp(pub PowerUpKind);
+
+#[derive(Resource)]
+struct PowerUpTexture(GameAtlas);
+
+/// Timed power-ups currently held by the player.
+///
+/// Stacking rules: picking up a timed power-up that is already active adds its
+/// duration to the remaining time, up to `POWERUP_STACK_MAX` durations. A shield
+/// also adds one charge, up to `SHIELD_CHARGES_MAX`, and each charge absorbs one hit.
+#[derive(Resource, Default)]
+pub struct ActivePowerUps {
+    timers: HashMap<PowerUpKind, Timer>,
+    pub shield_charges: u32,
+}
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.timers.contains_key(&kind)
+    }
+
+    fn grant(&mut self, kind: PowerUpKind) {
+        let duration = Duration::from_secs_f32(POWERUP_DURATION);
+        let remaining = self
+            .timers
+            .get(&kind)
+            .map_or(Duration::ZERO, Timer::remaining);
+        let total = (remaining + duration).min(duration.mul_f32(POWERUP_STACK_MAX));
+        self.timers.insert(kind, Timer::new(total, TimerMode::Once));
+
+        if kind == PowerUpKind::Shield {
+            self.shield_charges = (self.shield_charges + 1).min(SHIELD_CHARGES_MAX);
+        }
+    }
+
+    /// Use up a shield charge. Returns false when there was none left.
+    pub fn absorb_hit(&mut self) -> bool {
+        if self.shield_charges == 0 {
+            return false;
+        }
+        self.shield_charges -= 1;
+        if self.shield_charges == 0 {
+            self.timers.remove(&PowerUpKind::Shield);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        self.shield_charges = 0;
+    }
+
+    /// Active power-ups with their remaining seconds, in a stable order.
+    pub fn remaining(&self) -> Vec<(PowerUpKind, f32)> {
+        PowerUpKind::ALL
+            .iter()
+            .filter_map(|kind| {
+                self.timers
+                    .get(kind)
+                    .map(|timer| (*kind, timer.remaining_secs()))
+            })
+            .collect()
+    }
+}
+
+fn powerup_setup_system(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    // white disc, tinted per kind through the sprite color. It grows in, then a
+    // halo pulses around it; the frames leave room for the halo.
+    let radius = POWERUP_SIZE / 2.;
+    let frame_size = UVec2::splat((POWERUP_SIZE * 1.5) as u32);
+    let frames = POWERUP_GROW_FRAMES + POWERUP_PULSE_FRAMES;
+    let image = strip(frame_size, frames, |frame, offset| {
+        let distance = offset.length();
+        let (disc, halo) = if frame < POWERUP_GROW_FRAMES {
+            ((frame + 1) as f32 / POWERUP_GROW_FRAMES as f32 * radius, 0.)
+        } else {
+            let pulse = (frame - POWERUP_GROW_FRAMES) as f32 / (POWERUP_PULSE_FRAMES - 1) as f32;
+            (radius, pulse * radius / 2.)
+        };
+        let alpha = if distance < disc {
+            (disc - distance) * 255.
+        } else if distance < disc + halo {
+            // fades out towards the outer edge of the halo
+            (disc + halo - distance) / halo * 90.
+        } else {
+            0.
+        };
+        [255, 255, 255, alpha.min(255.) as u8]
+    });
+    let atlas = TextureAtlas::from_grid(
+        images.add(image),
+        frame_size.as_vec2(),
+        frames as usize,
+        1,
+        None,
+        None,
+    );
+
+    let grow = POWERUP_GROW_FRAMES as usize;
+    let animations = HashMap::from_iter([
+        (
+            "appear".to_string(),
+            AnimationClip {
+                first: 0,
+                last: grow - 1,
+                frame_time: 0.06,
+                frame_times: Vec::new(),
+                mode: LoopMode::Once,
+                on_finish: OnFinish::Chain("pulse".into()),
+            },
+        ),
+        (
+            "pulse".to_string(),
+            AnimationClip {
+                first: grow,
+                last: frames as usize - 1,
+                frame_time: 0.1,
+                // linger on the bare disc
+                frame_times: vec![0.4],
+                mode: LoopMode::PingPong,
+                on_finish: OnFinish::Event,
+            },
+        ),
+    ]);
+    commands.insert_resource(PowerUpTexture(GameAtlas {
+        atlas: texture_atlases.add(atlas),
+        animations: Arc::new(animations),
+    }));
+}
+
+fn powerup_to_spawn_system(
+    mut commands: Commands,
+    powerup_texture: Res<PowerUpTexture>,
+    game_font: Res<GameFont>,
+    query: Query<(Entity, &PowerUpToSpawn)>,
+) {
+    for (entity, powerup_to_spawn) in query.iter() {
+        let kind = *PowerUpKind::ALL.choose(&mut thread_rng()).unwrap();
+        let animation = Animation::new(&powerup_texture.0.animations, "appear");
+        commands
+            .spawn((
+                SpriteSheetBundle {
+                    texture_atlas: powerup_texture.0.atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: animation.index(),
+                        color: kind.color(),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(powerup_to_spawn.0.xy().extend(8.)),
+                    ..default()
+                },
+                PowerUp(kind),
+                animation,
+                SpriteSize::from((POWERUP_SIZE, POWERUP_SIZE)),
+                Movable { auto_despawn: true },
+                // falls once it has appeared
+                Velocity { x: 0., y: 0. },
+                Name::new("PowerUp"),
+            ))
+            .with_children(|parent| {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        kind.label(),
+                        TextStyle {
+                            font: game_font.0.clone(),
+                            font_size: 16.,
+                            color: Color::BLACK,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0., 0., 1.),
+                    ..default()
+                });
+            });
+
+        commands.entity(entity).despawn();
+    }
+}
+
+fn powerup_appeared_system(
+    mut finished_events: EventReader<AnimationFinished>,
+    mut query: Query<&mut Velocity, With<PowerUp>>,
+) {
+    for event in finished_events.iter() {
+        if event.clip != "appear" {
+            continue;
+        }
+        if let Ok(mut velocity) = query.get_mut(event.entity) {
+            velocity.y = -POWERUP_FALL_SPEED;
+        }
+    }
+}
+
+fn player_pickup_powerup_system(
+    mut commands: Commands,
+    mut active: ResMut<ActivePowerUps>,
+    mut player_state: ResMut<PlayerState>,
+    player_query: Query<(&Transform, &Hitbox), With<Player>>,
+    powerup_query: Query<(Entity, &Transform, &SpriteSize, &PowerUp)>,
+) {
+    let Ok((player_transform, player_size)) = player_query.get_single() else {
+        return;
+    };
+
+    for (powerup_entity, powerup_transform, powerup_size, powerup) in powerup_query.iter() {
+        let collision = collide(
+            player_transform.translation,
+            player_size.0 * player_transform.scale.xy(),
+            powerup_transform.translation,
+            powerup_size.0 * powerup_transform.scale.xy(),
+        );
+        if collision.is_none() {
+            continue;
+        }
+
+        match powerup.0 {
+            PowerUpKind::ExtraLife => {
+                player_state.lives = (player_state.lives + 1).min(PLAYER_LIVES_MAX);
+            }
+            PowerUpKind::Bomb => {
+                player_state.bombs = (player_state.bombs + 1).min(PLAYER_BOMBS_MAX);
+            }
+            kind => active.grant(kind),
+        }
+        commands.entity(powerup_entity).despawn_recursive();
+    }
+}
+
+fn powerup_timer_system(time: Res<Time>, mut active: ResMut<ActivePowerUps>) {
+    let active = active.as_mut();
+    active
+        .timers
+        .retain(|_, timer| !timer.tick(time.delta()).finished());
+    if !active.timers.contains_key(&PowerUpKind::Shield) {
+        active.shield_charges = 0;
+    }
+}
+
+fn player_shield_visual_system(
+    active: Res<ActivePowerUps>,
+    mut query: Query<&mut Sprite, With<Player>>,
+) {
+    for mut sprite in query.iter_mut() {
+        sprite.color = if active.is_active(PowerUpKind::Shield) {
+            Color::rgb(0.5, 0.9, 1.)
+        } else {
+            Color::WHITE
+        };
+    }
+}
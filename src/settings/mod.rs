@@ -0,0 +1,684 @@
+use crate::menu::{spawn_menu, MenuEvent, MenuItem};
+use crate::wave::{Difficulty, Wave, WaveStarted};
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode, WindowResolution};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const SETTINGS_DIR: &str = "rust-invaders";
+const SETTINGS_FILE: &str = "settings.toml";
+/// window sizes offered by the options screen
+const RESOLUTIONS: [(u32, u32); 4] = [(500, 700), (600, 840), (750, 1050), (1000, 1400)];
+const VOLUME_STEP: f64 = 0.1;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems((
+            settings_apply_system,
+            settings_difficulty_system,
+            options_menu_system,
+            options_label_system.after(options_menu_system),
+        ));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DifficultyLevel {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyLevel {
+    /// Make a wave's tuning easier or harder.
+    pub fn apply(&self, difficulty: &mut Difficulty) {
+        let (interval, lasers) = match self {
+            DifficultyLevel::Easy => (1.4, 0.7),
+            DifficultyLevel::Normal => (1., 1.),
+            DifficultyLevel::Hard => (0.75, 1.3),
+        };
+        difficulty.fire_interval *= interval;
+        difficulty.max_enemy_lasers = (difficulty.max_enemy_lasers as f32 * lasers) as usize;
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+impl Language {
+    /// The menu text `english` in this language.
+    pub fn tr(&self, english: &'static str) -> &'static str {
+        if *self == Language::English {
+            return english;
+        }
+        match english {
+            "LOADING" => "CHARGEMENT",
+            "COULD NOT LOAD" => "CHARGEMENT IMPOSSIBLE",
+            "PRESS ENTER TO CONTINUE" => "APPUYEZ SUR ENTREE POUR CONTINUER",
+            "START" => "JOUER",
+            "MODE SELECT" => "MODE DE JEU",
+            "OPTIONS" => "REGLAGES",
+            "HIGH SCORES" => "MEILLEURS SCORES",
+            "CONTROLS" => "COMMANDES",
+            "QUIT" => "QUITTER",
+            "MODE" => "TYPE DE PARTIE",
+            "HARDCORE" => "EXTREME",
+            "BEST THIS SESSION" => "MEILLEUR DE LA SESSION",
+            "PAUSED" => "PAUSE",
+            "RESUME" => "REPRENDRE",
+            "RESTART" => "RECOMMENCER",
+            "QUIT TO TITLE" => "MENU PRINCIPAL",
+            "SELECT YOUR SHIP" => "CHOISISSEZ VOTRE VAISSEAU",
+            "PLAYER A" => "JOUEUR A",
+            "PLAYER B" => "JOUEUR B",
+            "SPEED" => "VITESSE",
+            "GUNS" => "CANONS",
+            "LEFT/RIGHT TO CHOOSE, ENTER TO START, ESC TO GO BACK" => {
+                "GAUCHE/DROITE POUR CHOISIR, ENTREE POUR JOUER, ECHAP POUR REVENIR"
+            }
+            "HI" => "RECORD",
+            "WAVE" => "VAGUE",
+            "BOSS" => "GARDIEN",
+            "SPREAD" => "DISPERSION",
+            "RAPID" => "RAFALE",
+            "SHIELD" => "BOUCLIER",
+            "1UP" => "+1 VIE",
+            "BOMB" => "BOMBE",
+            "MOVE" => "DEPLACER",
+            "FIRE" => "TIRER",
+            "WEAPON" => "ARME",
+            "DASH" => "ESQUIVE",
+            "PAUSE" => "SUSPENDRE",
+            "ARROWS / WASD" => "FLECHES / WASD",
+            "SPACE" => "ESPACE",
+            "LEFT SHIFT" => "MAJ GAUCHE",
+            "ESC / P" => "ECHAP / P",
+            "GAME OVER" => "PARTIE TERMINEE",
+            "SCORE" => "POINTS",
+            "NEW HIGH SCORE" => "NOUVEAU RECORD",
+            "BACK" => "RETOUR",
+            "MASTER VOLUME" => "VOLUME GENERAL",
+            "MUSIC VOLUME" => "VOLUME MUSIQUE",
+            "SFX VOLUME" => "VOLUME EFFETS",
+            "WINDOW MODE" => "AFFICHAGE",
+            "RESOLUTION" => "DEFINITION",
+            "VSYNC" => "SYNCHRO VERTICALE",
+            "SCREEN SHAKE" => "TREMBLEMENT",
+            "DIFFICULTY" => "DIFFICULTE",
+            "LANGUAGE" => "LANGUE",
+            "WINDOWED" => "FENETRE",
+            "BORDERLESS" => "SANS BORDURE",
+            "FULLSCREEN" => "PLEIN ECRAN",
+            "EASY" => "FACILE",
+            "NORMAL" => "STANDARD",
+            "HARD" => "DIFFICILE",
+            "ON" => "OUI",
+            "OFF" => "NON",
+            "ENGLISH" => "ANGLAIS",
+            "FRENCH" => "FRANCAIS",
+            _ => english,
+        }
+    }
+}
+
+/// Player preferences, saved as TOML in the user's config directory.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub master_volume: f64,
+    /// kept for when the game gets music, the options screen leaves it out until then
+    pub music_volume: f64,
+    pub sfx_volume: f64,
+    pub window_mode: DisplayMode,
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+    /// 0 turns screen shake off
+    pub screen_shake: f64,
+    pub difficulty: DifficultyLevel,
+    pub language: Language,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 0.8,
+            music_volume: 0.7,
+            sfx_volume: 1.,
+            window_mode: DisplayMode::Windowed,
+            resolution: RESOLUTIONS[0],
+            vsync: true,
+            screen_shake: 1.,
+            difficulty: DifficultyLevel::Normal,
+            language: Language::English,
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(SETTINGS_DIR).join(SETTINGS_FILE))
+    }
+
+    /// Read the settings file, a missing file gives the defaults. This runs before
+    /// the app and its logger exist, so problems go straight to stderr.
+    pub fn load() -> Self {
+        let Some(path) = Settings::path() else {
+            return Settings::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Settings::default();
+        };
+        Settings::parse(&content, &path.display().to_string())
+    }
+
+    /// Settings from the TOML `content` of the file `source`. Unknown keys and
+    /// invalid values are reported and replaced by their default, content that
+    /// isn't TOML gives the defaults.
+    fn parse(content: &str, source: &str) -> Self {
+        let mut settings = Settings::default();
+        let table = match content.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(err) => {
+                eprintln!("{source}: {err}, using default settings");
+                return settings;
+            }
+        };
+
+        for (key, value) in table {
+            match key.as_str() {
+                "master_volume" => read(&key, value, &mut settings.master_volume),
+                "music_volume" => read(&key, value, &mut settings.music_volume),
+                "sfx_volume" => read(&key, value, &mut settings.sfx_volume),
+                "window_mode" => read(&key, value, &mut settings.window_mode),
+                "resolution" => read(&key, value, &mut settings.resolution),
+                "vsync" => read(&key, value, &mut settings.vsync),
+                "screen_shake" => read(&key, value, &mut settings.screen_shake),
+                "difficulty" => read(&key, value, &mut settings.difficulty),
+                "language" => read(&key, value, &mut settings.language),
+                _ => eprintln!("unknown setting `{key}` ignored"),
+            }
+        }
+        settings.validate();
+        settings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Settings::path() else {
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                fs::create_dir_all(path.parent().unwrap()).map_err(|err| err.to_string())?;
+                fs::write(&path, content).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("could not save settings to {}: {err}", path.display());
+        }
+    }
+
+    /// Replace out of range values by their default.
+    fn validate(&mut self) {
+        let default = Settings::default();
+        for (key, value, default) in [
+            (
+                "master_volume",
+                &mut self.master_volume,
+                default.master_volume,
+            ),
+            ("music_volume", &mut self.music_volume, default.music_volume),
+            ("sfx_volume", &mut self.sfx_volume, default.sfx_volume),
+            ("screen_shake", &mut self.screen_shake, default.screen_shake),
+        ] {
+            if !(0. ..=1.).contains(value) {
+                eprintln!("setting `{key}` = {value} is not between 0 and 1, using {default}");
+                *value = default;
+            }
+        }
+        if self.resolution.0 < 200 || self.resolution.1 < 200 {
+            eprintln!(
+                "setting `resolution` = {:?} is too small, using {:?}",
+                self.resolution, default.resolution
+            );
+            self.resolution = default.resolution;
+        }
+    }
+
+    /// Volume of sound effects, master volume included.
+    pub fn sfx(&self) -> PlaybackSettings {
+        PlaybackSettings::ONCE.with_volume((self.master_volume * self.sfx_volume) as f32)
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        match self.window_mode {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    pub fn window_resolution(&self) -> WindowResolution {
+        (self.resolution.0 as f32, self.resolution.1 as f32).into()
+    }
+}
+
+fn read<T: DeserializeOwned>(key: &str, value: toml::Value, field: &mut T) {
+    match value.try_into() {
+        Ok(value) => *field = value,
+        Err(err) => eprintln!(
+            "invalid setting `{key}`: {}, using the default",
+            err.to_string().trim_end()
+        ),
+    }
+}
+
+/// Push settings changes to the window and the settings file.
+fn settings_apply_system(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied_resolution: Local<Option<(u32, u32)>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    // the window opens at the saved resolution, after that only a new choice in
+    // the options resizes it, so a window resized by hand stays as it is
+    let resolution_changed = applied_resolution
+        .replace(settings.resolution)
+        .is_some_and(|applied| applied != settings.resolution);
+    if let Ok(mut window) = window_query.get_single_mut() {
+        if resolution_changed {
+            window.resolution = settings.window_resolution();
+        }
+        window.mode = settings.window_mode();
+        window.present_mode = settings.present_mode();
+    }
+    // the file was just read when the resource is new
+    if !settings.is_added() {
+        settings.save();
+    }
+}
+
+/// Retune each new wave for the difficulty setting.
+fn settings_difficulty_system(
+    settings: Res<Settings>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut wave: ResMut<Wave>,
+) {
+    if wave_started.is_empty() && !settings.is_changed() {
+        return;
+    }
+    wave_started.clear();
+    let mut difficulty = Difficulty::for_wave(wave.number);
+    settings.difficulty.apply(&mut difficulty);
+    wave.difficulty = difficulty;
+}
+
+/// One line of the options screen.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum OptionEntry {
+    MasterVolume,
+    SfxVolume,
+    WindowMode,
+    Resolution,
+    Vsync,
+    ScreenShake,
+    Difficulty,
+    Language,
+    /// leaves the options screen, handled by the screen showing it
+    Back,
+}
+
+impl OptionEntry {
+    pub const ALL: [OptionEntry; 8] = [
+        OptionEntry::MasterVolume,
+        OptionEntry::SfxVolume,
+        OptionEntry::WindowMode,
+        OptionEntry::Resolution,
+        OptionEntry::Vsync,
+        OptionEntry::ScreenShake,
+        OptionEntry::Difficulty,
+        OptionEntry::Language,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OptionEntry::MasterVolume => "MASTER VOLUME",
+            OptionEntry::SfxVolume => "SFX VOLUME",
+            OptionEntry::WindowMode => "WINDOW MODE",
+            OptionEntry::Resolution => "RESOLUTION",
+            OptionEntry::Vsync => "VSYNC",
+            OptionEntry::ScreenShake => "SCREEN SHAKE",
+            OptionEntry::Difficulty => "DIFFICULTY",
+            OptionEntry::Language => "LANGUAGE",
+            OptionEntry::Back => "BACK",
+        }
+    }
+
+    /// Menu label with the current value.
+    pub fn label(&self, settings: &Settings) -> String {
+        let language = settings.language;
+        if *self == OptionEntry::Back {
+            return language.tr(self.name()).to_string();
+        }
+        let percent = |value: f64| format!("{:.0}%", value * 100.);
+        let value = match self {
+            OptionEntry::MasterVolume => percent(settings.master_volume),
+            OptionEntry::SfxVolume => percent(settings.sfx_volume),
+            OptionEntry::WindowMode => language
+                .tr(match settings.window_mode {
+                    DisplayMode::Windowed => "WINDOWED",
+                    DisplayMode::Borderless => "BORDERLESS",
+                    DisplayMode::Fullscreen => "FULLSCREEN",
+                })
+                .to_string(),
+            OptionEntry::Resolution => {
+                format!("{}x{}", settings.resolution.0, settings.resolution.1)
+            }
+            OptionEntry::Vsync => language
+                .tr(if settings.vsync { "ON" } else { "OFF" })
+                .to_string(),
+            OptionEntry::ScreenShake => percent(settings.screen_shake),
+            OptionEntry::Difficulty => language
+                .tr(match settings.difficulty {
+                    DifficultyLevel::Easy => "EASY",
+                    DifficultyLevel::Normal => "NORMAL",
+                    DifficultyLevel::Hard => "HARD",
+                })
+                .to_string(),
+            OptionEntry::Language => language
+                .tr(match settings.language {
+                    Language::English => "ENGLISH",
+                    Language::French => "FRENCH",
+                })
+                .to_string(),
+            OptionEntry::Back => String::new(),
+        };
+        format!("{} {}", language.tr(self.name()), value)
+    }
+
+    /// Step the setting forward (`step` > 0) or backward.
+    fn adjust(&self, settings: &mut Settings, step: i32) {
+        let volume = |value: &mut f64| {
+            *value = (*value + step as f64 * VOLUME_STEP).clamp(0., 1.);
+            // keep round percentages despite float steps
+            *value = (*value * 10.).round() / 10.;
+        };
+        match self {
+            OptionEntry::MasterVolume => volume(&mut settings.master_volume),
+            OptionEntry::SfxVolume => volume(&mut settings.sfx_volume),
+            OptionEntry::ScreenShake => volume(&mut settings.screen_shake),
+            OptionEntry::WindowMode => {
+                let modes = [
+                    DisplayMode::Windowed,
+                    DisplayMode::Borderless,
+                    DisplayMode::Fullscreen,
+                ];
+                settings.window_mode = cycle(&modes, settings.window_mode, step);
+            }
+            OptionEntry::Resolution => {
+                settings.resolution = cycle(&RESOLUTIONS, settings.resolution, step);
+            }
+            OptionEntry::Vsync => settings.vsync = !settings.vsync,
+            OptionEntry::Difficulty => {
+                let levels = [
+                    DifficultyLevel::Easy,
+                    DifficultyLevel::Normal,
+                    DifficultyLevel::Hard,
+                ];
+                settings.difficulty = cycle(&levels, settings.difficulty, step);
+            }
+            OptionEntry::Language => {
+                let languages = [Language::English, Language::French];
+                settings.language = cycle(&languages, settings.language, step);
+            }
+            OptionEntry::Back => {}
+        }
+    }
+}
+
+/// Spawn the options menu under `parent`, its labels follow `Settings`.
+pub fn spawn_options_menu(parent: &mut ChildBuilder, font: &Handle<Font>, settings: &Settings) {
+    let entries: Vec<_> = OptionEntry::ALL
+        .into_iter()
+        .chain([OptionEntry::Back])
+        .map(|entry| (entry.label(settings), entry))
+        .collect();
+    spawn_menu(
+        parent,
+        font,
+        entries
+            .iter()
+            .map(|(label, entry)| (label.as_str(), *entry))
+            .collect(),
+    );
+}
+
+/// The value `step` places after `current` in `values`, wrapping around.
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: i32) -> T {
+    let index = values
+        .iter()
+        .position(|value| *value == current)
+        .unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
+
+/// Options entries are changed with left/right, confirm steps forward.
+fn options_menu_system(
+    mut menu_events: EventReader<MenuEvent>,
+    mut settings: ResMut<Settings>,
+    option_query: Query<&OptionEntry>,
+) {
+    for event in menu_events.iter() {
+        let (item, step) = match event {
+            MenuEvent::Confirm { item } => (*item, 1),
+            MenuEvent::Adjust { item, step } => (*item, *step),
+            MenuEvent::Back => continue,
+        };
+        if let Ok(entry) = option_query.get(item) {
+            entry.adjust(&mut settings, step);
+        }
+    }
+}
+
+fn options_label_system(settings: Res<Settings>, mut query: Query<(&OptionEntry, &mut MenuItem)>) {
+    for (entry, mut item) in query.iter_mut() {
+        let label = entry.label(&settings);
+        if item.label != label {
+            item.label = label;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::powerup::PowerUpKind;
+    use crate::ship::SHIPS;
+    use crate::title::GameMode;
+
+    fn parse(content: &str) -> Settings {
+        Settings::parse(content, "settings.toml")
+    }
+
+    #[test]
+    fn parses_every_setting() {
+        let settings = parse(
+            r#"
+            master_volume = 0.5
+            music_volume = 0.2
+            sfx_volume = 0.3
+            window_mode = "borderless"
+            resolution = [600, 840]
+            vsync = false
+            screen_shake = 0.0
+            difficulty = "hard"
+            language = "french"
+            "#,
+        );
+        assert_eq!(
+            settings,
+            Settings {
+                master_volume: 0.5,
+                music_volume: 0.2,
+                sfx_volume: 0.3,
+                window_mode: DisplayMode::Borderless,
+                resolution: (600, 840),
+                vsync: false,
+                screen_shake: 0.,
+                difficulty: DifficultyLevel::Hard,
+                language: Language::French,
+            }
+        );
+    }
+
+    #[test]
+    fn saved_settings_read_back() {
+        let settings = Settings {
+            sfx_volume: 0.4,
+            language: Language::French,
+            ..default()
+        };
+        assert_eq!(parse(&toml::to_string(&settings).unwrap()), settings);
+    }
+
+    #[test]
+    fn ignores_unknown_keys() {
+        assert_eq!(
+            parse("sfx_volume = 0.5\nfov = 90"),
+            Settings {
+                sfx_volume: 0.5,
+                ..default()
+            }
+        );
+    }
+
+    #[test]
+    fn wrong_type_uses_the_default() {
+        let settings = parse("vsync = \"sometimes\"\ndifficulty = \"brutal\"\nsfx_volume = 0.5");
+        assert_eq!(settings.vsync, Settings::default().vsync);
+        assert_eq!(settings.difficulty, Settings::default().difficulty);
+        // the other keys are still read
+        assert_eq!(settings.sfx_volume, 0.5);
+    }
+
+    #[test]
+    fn out_of_range_values_reset() {
+        let settings = parse("master_volume = 1.5\nscreen_shake = -0.2\nresolution = [100, 700]");
+        let default = Settings::default();
+        assert_eq!(settings.master_volume, default.master_volume);
+        assert_eq!(settings.screen_shake, default.screen_shake);
+        assert_eq!(settings.resolution, default.resolution);
+    }
+
+    #[test]
+    fn malformed_file_gives_the_defaults() {
+        assert_eq!(parse("master_volume = [0.5"), Settings::default());
+        assert_eq!(parse("not toml at all"), Settings::default());
+    }
+
+    /// Every text the screens pass to `tr`, besides the names listed by their types.
+    /// Key names like `B` read the same in both languages and are left out.
+    const UI_TEXT: [&str; 46] = [
+        // HUD
+        "SCORE",
+        "HI",
+        "WAVE",
+        "BOSS",
+        "BOMB",
+        // loading
+        "LOADING",
+        "COULD NOT LOAD",
+        "PRESS ENTER TO CONTINUE",
+        // title, pause and game over
+        "START",
+        "MODE SELECT",
+        "OPTIONS",
+        "HIGH SCORES",
+        "CONTROLS",
+        "QUIT",
+        "MODE",
+        "BEST THIS SESSION",
+        "PAUSED",
+        "RESUME",
+        "RESTART",
+        "QUIT TO TITLE",
+        "BACK",
+        "GAME OVER",
+        "NEW HIGH SCORE",
+        // controls page
+        "MOVE",
+        "FIRE",
+        "WEAPON",
+        "DASH",
+        "PAUSE",
+        "ARROWS / WASD",
+        "SPACE",
+        "LEFT SHIFT",
+        "ESC / P",
+        // ship select
+        "SELECT YOUR SHIP",
+        "SPEED",
+        "GUNS",
+        "LEFT/RIGHT TO CHOOSE, ENTER TO START, ESC TO GO BACK",
+        // option values
+        "WINDOWED",
+        "BORDERLESS",
+        "FULLSCREEN",
+        "ON",
+        "OFF",
+        "EASY",
+        "NORMAL",
+        "HARD",
+        "ENGLISH",
+        "FRENCH",
+    ];
+
+    #[test]
+    fn french_covers_the_ui() {
+        let names = PowerUpKind::ALL
+            .iter()
+            .map(PowerUpKind::name)
+            .chain(SHIPS.iter().map(|ship| ship.name))
+            .chain(
+                [GameMode::Normal, GameMode::Hardcore]
+                    .iter()
+                    .map(GameMode::name),
+            )
+            .chain(OptionEntry::ALL.iter().map(OptionEntry::name));
+        for english in UI_TEXT.into_iter().chain(names) {
+            assert_ne!(
+                Language::French.tr(english),
+                english,
+                "no French for `{english}`"
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use crate::menu::{MenuInput, MenuInputSet};
+use crate::settings::Settings;
+use crate::{AppState, GameAudio, GameFont, GameTexture, SPRITE_SCALE};
+use bevy::prelude::*;
+
+pub struct ShipPlugin;
+
+impl Plugin for ShipPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SelectedShip::default())
+            .add_system(ship_select_setup_system.in_schedule(OnEnter(AppState::ShipSelect)))
+            .add_system(
+                ship_select_system
+                    .after(MenuInputSet)
+                    .in_set(OnUpdate(AppState::ShipSelect)),
+            )
+            .add_system(ship_select_cleanup_system.in_schedule(OnExit(AppState::ShipSelect)));
+    }
+}
+
+/// Guns of a ship, in sprite pixels relative to its center.
+pub struct Loadout {
+    pub muzzles: &'static [(f32, f32)],
+    /// projectiles per muzzle and per volley, the spread shot power-up adds 2
+    pub ways: u32,
+    /// arc in radians covered by the `ways` projectiles
+    pub spread: f32,
+}
+
+/// A playable ship. Its sizes come from the sprite manifest.
+pub struct ShipDef {
+    pub name: &'static str,
+    /// name of the ship's sprite in the manifest
+    pub sprite: &'static str,
+    /// multiplies the base speed
+    pub speed: f32,
+    pub loadout: Loadout,
+}
+
+pub const SHIPS: [ShipDef; 2] = [
+    ShipDef {
+        name: "PLAYER A",
+        sprite: "player_a",
+        speed: 1.,
+        loadout: Loadout {
+            muzzles: &[(62., 0.), (-62., 0.)],
+            ways: 1,
+            spread: 0.,
+        },
+    },
+    ShipDef {
+        name: "PLAYER B",
+        sprite: "player_b",
+        speed: 1.3,
+        loadout: Loadout {
+            muzzles: &[(0., 30.)],
+            ways: 2,
+            spread: 0.1,
+        },
+    },
+];
+
+/// Index in `SHIPS` of the ship picked for the run.
+#[derive(Resource, Default)]
+pub struct SelectedShip(pub usize);
+
+impl SelectedShip {
+    pub fn def(&self) -> &'static ShipDef {
+        &SHIPS[self.0]
+    }
+}
+
+#[derive(Component)]
+struct ShipSelectScreen;
+
+#[derive(Component)]
+struct ShipCard(usize);
+
+fn ship_select_setup_system(
+    mut commands: Commands,
+    game_font: Res<GameFont>,
+    game_texture: Res<GameTexture>,
+    settings: Res<Settings>,
+) {
+    let tr = |english| settings.language.tr(english);
+    let text_style = |font_size: f32| TextStyle {
+        font: game_font.0.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    // the stat values line up after the longer label
+    let (speed, guns) = (tr("SPEED"), tr("GUNS"));
+    let width = speed.len().max(guns.len()) + 1;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    gap: Size::all(Val::Px(24.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.85).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            ShipSelectScreen,
+            Name::new("ShipSelectScreen"),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                tr("SELECT YOUR SHIP"),
+                text_style(28.),
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        gap: Size::all(Val::Px(16.)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for (index, ship) in SHIPS.iter().enumerate() {
+                        row.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    padding: UiRect::all(Val::Px(12.)),
+                                    gap: Size::all(Val::Px(8.)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            ShipCard(index),
+                        ))
+                        .with_children(|card| {
+                            let sprite = &game_texture.ships[index];
+                            card.spawn(ImageBundle {
+                                image: UiImage::new(sprite.image.clone()),
+                                style: Style {
+                                    size: Size::new(
+                                        Val::Px(sprite.size.x * SPRITE_SCALE.0),
+                                        Val::Px(sprite.size.y * SPRITE_SCALE.1),
+                                    ),
+                                    ..default()
+                                },
+                                ..default()
+                            });
+                            card.spawn(TextBundle::from_section(tr(ship.name), text_style(18.)));
+                            card.spawn(TextBundle::from_section(
+                                format!(
+                                    "{:<width$}{:.1}\n{:<width$}{}x{}",
+                                    speed,
+                                    ship.speed,
+                                    guns,
+                                    ship.loadout.muzzles.len(),
+                                    ship.loadout.ways
+                                ),
+                                text_style(14.),
+                            ));
+                        });
+                    }
+                });
+            parent.spawn(TextBundle::from_section(
+                tr("LEFT/RIGHT TO CHOOSE, ENTER TO START, ESC TO GO BACK"),
+                text_style(14.),
+            ));
+        });
+}
+
+fn ship_select_system(
+    menu_input: Res<MenuInput>,
+    audio: Res<Audio>,
+    game_audio: Res<GameAudio>,
+    settings: Res<Settings>,
+    mut selected: ResMut<SelectedShip>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut card_query: Query<(&ShipCard, &mut BackgroundColor)>,
+) {
+    if menu_input.left {
+        selected.0 = (selected.0 + SHIPS.len() - 1) % SHIPS.len();
+        audio.play_with_settings(game_audio.menu_move.clone(), settings.sfx());
+    }
+    if menu_input.right {
+        selected.0 = (selected.0 + 1) % SHIPS.len();
+        audio.play_with_settings(game_audio.menu_move.clone(), settings.sfx());
+    }
+    if menu_input.confirm {
+        audio.play_with_settings(game_audio.menu_confirm.clone(), settings.sfx());
+        next_state.set(AppState::InGame);
+    } else if menu_input.back {
+        audio.play_with_settings(game_audio.menu_back.clone(), settings.sfx());
+        next_state.set(AppState::Title);
+    }
+
+    for (card, mut color) in card_query.iter_mut() {
+        *color = if card.0 == selected.0 {
+            Color::rgba(0.3, 0.6, 1., 0.35)
+        } else {
+            Color::NONE
+        }
+        .into();
+    }
+}
+
+fn ship_select_cleanup_system(
+    mut commands: Commands,
+    query: Query<Entity, With<ShipSelectScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
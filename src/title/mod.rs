@@ -0,0 +1,225 @@
+use crate::menu::{spawn_menu, MenuEvent};
+use crate::settings::{spawn_options_menu, OptionEntry, Settings};
+use crate::{AppState, GameFont, HighScore, PLAYER_BOMBS, PLAYER_LIVES};
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+/// action and keys of each line of the controls page
+const CONTROLS: [(&str, &str); 6] = [
+    ("MOVE", "ARROWS / WASD"),
+    ("FIRE", "SPACE"),
+    ("WEAPON", "1 2 3 4"),
+    ("DASH", "LEFT SHIFT"),
+    ("BOMB", "B"),
+    ("PAUSE", "ESC / P"),
+];
+
+pub struct TitlePlugin;
+
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameMode::default())
+            .insert_resource(TitlePage::default())
+            .add_system(title_enter_system.in_schedule(OnEnter(AppState::Title)))
+            .add_system(title_cleanup_system.in_schedule(OnExit(AppState::Title)))
+            .add_systems((title_page_system, title_menu_system).in_set(OnUpdate(AppState::Title)));
+    }
+}
+
+/// Rules of a run, picked from the mode select page.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    /// a single life and no bombs
+    Hardcore,
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Normal => "NORMAL",
+            GameMode::Hardcore => "HARDCORE",
+        }
+    }
+
+    pub fn lives(&self) -> u32 {
+        match self {
+            GameMode::Normal => PLAYER_LIVES,
+            GameMode::Hardcore => 1,
+        }
+    }
+
+    pub fn bombs(&self) -> u32 {
+        match self {
+            GameMode::Normal => PLAYER_BOMBS,
+            GameMode::Hardcore => 0,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum TitlePage {
+    #[default]
+    Main,
+    Mode,
+    Options,
+    HighScores,
+    Controls,
+}
+
+#[derive(Component, Clone, Copy)]
+enum TitleAction {
+    Start,
+    Page(TitlePage),
+    Mode(GameMode),
+    Quit,
+}
+
+#[derive(Component)]
+struct TitleScreen;
+
+fn title_enter_system(mut page: ResMut<TitlePage>) {
+    // rebuild the screen even when coming back to the main page
+    *page = TitlePage::Main;
+    page.set_changed();
+}
+
+fn title_cleanup_system(mut commands: Commands, query: Query<Entity, With<TitleScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn title_page_system(
+    mut commands: Commands,
+    page: Res<TitlePage>,
+    game_font: Res<GameFont>,
+    game_mode: Res<GameMode>,
+    high_score: Res<HighScore>,
+    settings: Res<Settings>,
+    query: Query<Entity, With<TitleScreen>>,
+) {
+    // the options entries relabel themselves, other pages pick up the language when shown
+    if !page.is_changed() {
+        return;
+    }
+    let tr = |english| settings.language.tr(english);
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let text = |value: String, font_size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: game_font.0.clone(),
+                font_size,
+                color: Color::WHITE,
+            },
+        )
+    };
+    let back = || vec![(tr("BACK"), TitleAction::Page(TitlePage::Main))];
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    gap: Size::all(Val::Px(32.)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.04, 0.04, 0.04).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            TitleScreen,
+            Name::new("TitleScreen"),
+        ))
+        .with_children(|parent| match *page {
+            TitlePage::Main => {
+                parent.spawn(text("RUST INVADERS!".into(), 40.));
+                spawn_menu(
+                    parent,
+                    &game_font.0,
+                    vec![
+                        (tr("START"), TitleAction::Start),
+                        (tr("MODE SELECT"), TitleAction::Page(TitlePage::Mode)),
+                        (tr("OPTIONS"), TitleAction::Page(TitlePage::Options)),
+                        (tr("HIGH SCORES"), TitleAction::Page(TitlePage::HighScores)),
+                        (tr("CONTROLS"), TitleAction::Page(TitlePage::Controls)),
+                        (tr("QUIT"), TitleAction::Quit),
+                    ],
+                );
+            }
+            TitlePage::Mode => {
+                parent.spawn(text(
+                    format!("{}: {}", tr("MODE"), tr(game_mode.name())),
+                    28.,
+                ));
+                spawn_menu(
+                    parent,
+                    &game_font.0,
+                    [GameMode::Normal, GameMode::Hardcore]
+                        .into_iter()
+                        .map(|mode| (tr(mode.name()), TitleAction::Mode(mode)))
+                        .collect(),
+                );
+            }
+            TitlePage::Options => {
+                parent.spawn(text(tr("OPTIONS").into(), 28.));
+                spawn_options_menu(parent, &game_font.0, &settings);
+            }
+            TitlePage::HighScores => {
+                parent.spawn(text(tr("HIGH SCORES").into(), 28.));
+                parent.spawn(text(
+                    format!("{}  {}", tr("BEST THIS SESSION"), high_score.value),
+                    16.,
+                ));
+                spawn_menu(parent, &game_font.0, back());
+            }
+            TitlePage::Controls => {
+                parent.spawn(text(tr("CONTROLS").into(), 28.));
+                let lines: Vec<String> = CONTROLS
+                    .iter()
+                    .map(|&(action, keys)| format!("{:<12}{}", tr(action), tr(keys)))
+                    .collect();
+                parent.spawn(text(lines.join("\n"), 16.));
+                spawn_menu(parent, &game_font.0, back());
+            }
+        });
+}
+
+fn title_menu_system(
+    mut menu_events: EventReader<MenuEvent>,
+    mut page: ResMut<TitlePage>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+    action_query: Query<&TitleAction>,
+    option_query: Query<&OptionEntry>,
+) {
+    for event in menu_events.iter() {
+        let action = match event {
+            MenuEvent::Confirm { item } if option_query.get(*item) == Ok(&OptionEntry::Back) => {
+                Some(TitleAction::Page(TitlePage::Main))
+            }
+            MenuEvent::Confirm { item } => action_query.get(*item).ok().copied(),
+            MenuEvent::Adjust { .. } => None,
+            MenuEvent::Back => Some(TitleAction::Page(TitlePage::Main)),
+        };
+        match action {
+            Some(TitleAction::Start) => next_state.set(AppState::ShipSelect),
+            Some(TitleAction::Page(new_page)) if *page != new_page => *page = new_page,
+            Some(TitleAction::Mode(mode)) => {
+                *game_mode = mode;
+                *page = TitlePage::Main;
+            }
+            Some(TitleAction::Quit) => exit.send(AppExit),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::collision::world_extent;
+use crate::components::{
+    ExplosionToSpawn, FromPlayer, Hitbox, Laser, Movable, SpriteSize, Velocity,
+};
+use crate::settings::Settings;
+use crate::wave::Wave;
+use crate::{
+    spawn_floating_text, AppState, GameAudio, GameFont, GameTexture, Score, WinSize, UFO_BONUSES,
+    UFO_SCALE, UFO_SPAWN_INTERVAL, UFO_SPEED,
+};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::collide;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng};
+use std::time::Duration;
+
+pub struct UfoPlugin;
+
+impl Plugin for UfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UfoSpawnTimer(Timer::new(
+            random_spawn_delay(),
+            TimerMode::Once,
+        )))
+        .add_event::<UfoHit>()
+        .add_system(ufo_timer_reset_system.in_schedule(OnEnter(AppState::InGame)))
+        .add_systems(
+            (
+                ufo_spawn_system,
+                player_laser_hit_ufo_system,
+                ufo_hit_system.after(player_laser_hit_ufo_system),
+            )
+                .in_set(OnUpdate(AppState::InGame)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Ufo {
+    pub bonus: u32,
+}
+
+/// The UFO was shot, one hit brings it down.
+pub struct UfoHit(pub Entity);
+
+#[derive(Resource)]
+struct UfoSpawnTimer(Timer);
+
+fn random_spawn_delay() -> Duration {
+    let (min, max) = UFO_SPAWN_INTERVAL;
+    Duration::from_secs_f32(thread_rng().gen_range(min..max))
+}
+
+fn ufo_timer_reset_system(mut timer: ResMut<UfoSpawnTimer>) {
+    timer.0 = Timer::new(random_spawn_delay(), TimerMode::Once);
+}
+
+fn ufo_spawn_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut spawn_timer: ResMut<UfoSpawnTimer>,
+    wave: Res<Wave>,
+    game_texture: Res<GameTexture>,
+    game_audio: Res<GameAudio>,
+    settings: Res<Settings>,
+    audio: Res<Audio>,
+    win_size: Res<WinSize>,
+    ufo_query: Query<(), With<Ufo>>,
+) {
+    if !spawn_timer.0.tick(time.delta()).finished() {
+        return;
+    }
+    spawn_timer.0 = Timer::new(random_spawn_delay(), TimerMode::Once);
+
+    // one at a time, and never during a boss fight
+    if !ufo_query.is_empty() || wave.is_boss() {
+        return;
+    }
+
+    let mut rng = thread_rng();
+    let dir = if rng.gen_bool(0.5) { 1. } else { -1. };
+    let size = game_texture.enemy.size * UFO_SCALE;
+    let x = -dir * (win_size.width / 2. + size.x);
+    let y = win_size.height / 2. - size.y;
+
+    commands.spawn((
+        SpriteBundle {
+            texture: game_texture.enemy.image.clone(),
+            sprite: Sprite {
+                color: Color::rgb(1., 0.3, 0.9),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(x, y, 10.),
+                scale: Vec3::new(UFO_SCALE, UFO_SCALE, 1.),
+                ..default()
+            },
+            ..default()
+        },
+        Ufo {
+            bonus: *UFO_BONUSES.choose(&mut rng).unwrap(),
+        },
+        SpriteSize(game_texture.enemy.size),
+        Hitbox(game_texture.enemy.hitbox),
+        Movable { auto_despawn: true },
+        Velocity {
+            x: dir * UFO_SPEED,
+            y: 0.,
+        },
+        Name::new("Ufo"),
+    ));
+    audio.play_with_settings(game_audio.ufo.clone(), settings.sfx());
+}
+
+fn player_laser_hit_ufo_system(
+    mut commands: Commands,
+    mut ufo_hits: EventWriter<UfoHit>,
+    laser_query: Query<(Entity, &Transform, &Hitbox), (With<Laser>, With<FromPlayer>)>,
+    ufo_query: Query<(Entity, &Transform, &Hitbox), With<Ufo>>,
+) {
+    let Ok((ufo_entity, ufo_transform, ufo_hitbox)) = ufo_query.get_single() else {
+        return;
+    };
+
+    for (laser_entity, laser_transform, laser_hitbox) in laser_query.iter() {
+        let collision = collide(
+            laser_transform.translation,
+            world_extent(laser_hitbox.0, laser_transform),
+            ufo_transform.translation,
+            ufo_hitbox.0 * ufo_transform.scale.xy(),
+        );
+
+        if collision.is_some() {
+            commands.entity(laser_entity).despawn();
+            ufo_hits.send(UfoHit(ufo_entity));
+            break;
+        }
+    }
+}
+
+/// Bring down the UFO and award its bonus.
+fn ufo_hit_system(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    game_font: Res<GameFont>,
+    mut ufo_hits: EventReader<UfoHit>,
+    ufo_query: Query<(&Transform, &SpriteSize, &Ufo)>,
+) {
+    // there is a single UFO, the hits after the first one come too late
+    let Some((ufo_entity, (ufo_transform, ufo_size, ufo))) = ufo_hits
+        .iter()
+        .find_map(|UfoHit(entity)| Some((*entity, ufo_query.get(*entity).ok()?)))
+    else {
+        return;
+    };
+    ufo_hits.clear();
+
+    commands.entity(ufo_entity).despawn();
+    commands.spawn(ExplosionToSpawn {
+        translation: ufo_transform.translation,
+        size: ufo_size.0 * ufo_transform.scale.xy(),
+    });
+    spawn_floating_text(
+        &mut commands,
+        &game_font,
+        ufo.bonus.to_string(),
+        ufo_transform.translation,
+    );
+    score.value += ufo.bonus;
+}
@@ -0,0 +1,146 @@
+use crate::{AppState, BACKGROUND_COLOR};
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::time::Duration;
+
+const WAVE_KILLS: u32 = 10;
+/// every n-th wave is a boss wave
+const BOSS_WAVE_EVERY: u32 = 5;
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Wave::new(1))
+            .add_event::<WaveStarted>()
+            .add_system(wave_progress_system.in_set(OnUpdate(AppState::InGame)));
+    }
+}
+
+/// Tuning knobs that get harder as the waves go by.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difficulty {
+    /// average seconds between two volleys of the same enemy
+    pub fire_interval: f32,
+    /// random +/- fraction applied to `fire_interval`
+    pub fire_jitter: f32,
+    /// enemy lasers allowed on screen at once
+    pub max_enemy_lasers: usize,
+}
+
+impl Difficulty {
+    pub fn for_wave(number: u32) -> Self {
+        let level = number.saturating_sub(1) as f32;
+        Self {
+            fire_interval: (2.5 - level * 0.2).max(0.8),
+            fire_jitter: 0.4,
+            max_enemy_lasers: 12 + 4 * number.min(8) as usize,
+        }
+    }
+
+    /// Randomized delay until an enemy's next volley.
+    pub fn next_fire_delay(&self) -> Duration {
+        let jitter = thread_rng().gen_range(-self.fire_jitter..=self.fire_jitter);
+        Duration::from_secs_f32(self.fire_interval * (1. + jitter))
+    }
+}
+
+/// Look of the playfield during a wave.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaveBackground {
+    /// clear color, behind everything
+    pub color: Color,
+    /// tint of the star layers
+    pub stars: Color,
+    /// image scrolling behind the stars, stretched to the playfield
+    pub image: Option<&'static str>,
+}
+
+/// backgrounds of the regular waves, in turn
+const WAVE_BACKGROUNDS: [WaveBackground; 3] = [
+    WaveBackground {
+        color: BACKGROUND_COLOR,
+        stars: Color::WHITE,
+        image: None,
+    },
+    WaveBackground {
+        color: Color::rgb(0.03, 0.02, 0.07),
+        stars: Color::rgb(0.8, 0.85, 1.),
+        image: Some("nebula_a.png"),
+    },
+    WaveBackground {
+        color: Color::rgb(0.01, 0.05, 0.06),
+        stars: Color::rgb(0.85, 1., 0.95),
+        image: Some("nebula_b.png"),
+    },
+];
+const BOSS_BACKGROUND: WaveBackground = WaveBackground {
+    color: Color::rgb(0.08, 0.01, 0.02),
+    stars: Color::rgb(1., 0.75, 0.7),
+    image: None,
+};
+
+/// Whether wave `number` is fought against a boss.
+pub fn is_boss_wave(number: u32) -> bool {
+    number.is_multiple_of(BOSS_WAVE_EVERY)
+}
+
+impl WaveBackground {
+    fn for_wave(number: u32) -> Self {
+        if is_boss_wave(number) {
+            return BOSS_BACKGROUND;
+        }
+        // counting regular waves only, so boss waves don't skip a background
+        let level = number.saturating_sub(1);
+        let regular = level - level / BOSS_WAVE_EVERY;
+        WAVE_BACKGROUNDS[regular as usize % WAVE_BACKGROUNDS.len()].clone()
+    }
+}
+
+/// Sent when a wave begins, the first one of a run included. `Wave` itself
+/// changes on every kill, so systems follow the waves with this instead.
+pub struct WaveStarted;
+
+#[derive(Resource, Debug)]
+pub struct Wave {
+    pub number: u32,
+    pub kills: u32,
+    pub difficulty: Difficulty,
+    pub boss_spawned: bool,
+    pub background: WaveBackground,
+}
+
+impl Wave {
+    pub fn new(number: u32) -> Self {
+        Self {
+            number,
+            kills: 0,
+            difficulty: Difficulty::for_wave(number),
+            boss_spawned: false,
+            background: WaveBackground::for_wave(number),
+        }
+    }
+
+    pub fn is_boss(&self) -> bool {
+        is_boss_wave(self.number)
+    }
+
+    pub fn register_kill(&mut self) {
+        // boss waves only end when the boss goes down
+        if !self.is_boss() {
+            self.kills += 1;
+        }
+    }
+
+    pub fn complete(&mut self) {
+        self.kills = WAVE_KILLS;
+    }
+}
+
+fn wave_progress_system(mut wave: ResMut<Wave>, mut wave_started: EventWriter<WaveStarted>) {
+    if wave.kills >= WAVE_KILLS {
+        *wave = Wave::new(wave.number + 1);
+        wave_started.send(WaveStarted);
+        debug!("Wave {}: {:?}", wave.number, wave.difficulty);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::collision::ray_box;
+use crate::components::{Enemy, Hitbox, Player, SpriteSize};
+use crate::ufo::{Ufo, UfoHit};
+use crate::weapon::WeaponMode;
+use crate::{EnemyHit, FireSettings, PlayerState, WinSize};
+use bevy::math::Vec3Swizzles;
+use bevy::prelude::*;
+
+const BEAM_WIDTH: f32 = 6.;
+/// seconds between two damage ticks on the enemy the beam touches
+const BEAM_TICK: f32 = 0.1;
+/// heat gained per second of firing
+const BEAM_HEAT_RATE: f32 = 0.35;
+
+#[derive(Component)]
+pub struct Beam {
+    tick: Timer,
+}
+
+pub fn player_beam_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    settings: Res<FireSettings>,
+    win_size: Res<WinSize>,
+    mut player_state: ResMut<PlayerState>,
+    mut enemy_hits: EventWriter<EnemyHit>,
+    mut ufo_hits: EventWriter<UfoHit>,
+    player_query: Query<(&Transform, &SpriteSize), With<Player>>,
+    target_query: Query<
+        (Entity, &GlobalTransform, &Hitbox, Option<&Ufo>),
+        Or<(With<Enemy>, With<Ufo>)>,
+    >,
+    mut beam_query: Query<(Entity, &mut Transform, &mut Sprite, &mut Beam), Without<Player>>,
+) {
+    let firing = player_state.weapon_mode == WeaponMode::Beam
+        && !player_state.overheated
+        && keyboard_input.pressed(KeyCode::Space);
+    let Some((player_transform, player_size)) = player_query.get_single().ok().filter(|_| firing)
+    else {
+        for (beam_entity, ..) in beam_query.iter() {
+            commands.entity(beam_entity).despawn();
+        }
+        return;
+    };
+
+    // ray from the nose of the ship straight up, stopped by the first enemy or UFO
+    let origin = player_transform.translation.xy()
+        + Vec2::new(0., player_size.0.y / 2. * player_transform.scale.y);
+    let max_length = win_size.height / 2. - origin.y;
+    let contact = target_query
+        .iter()
+        .filter_map(|(entity, transform, hitbox, ufo)| {
+            let transform = transform.compute_transform();
+            ray_box(
+                origin,
+                Vec2::Y,
+                transform.translation.xy(),
+                hitbox.0 * transform.scale.xy(),
+            )
+            .map(|distance| (entity, distance, ufo.is_some()))
+        })
+        .filter(|(_, distance, _)| *distance <= max_length)
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+    let length = contact
+        .map_or(max_length, |(_, distance, _)| distance)
+        .max(0.);
+
+    let translation = (origin + Vec2::new(0., length / 2.)).extend(5.);
+    let size = Some(Vec2::new(BEAM_WIDTH, length));
+    match beam_query.get_single_mut() {
+        Ok((_, mut transform, mut sprite, mut beam)) => {
+            transform.translation = translation;
+            sprite.custom_size = size;
+            if beam.tick.tick(time.delta()).just_finished() {
+                match contact {
+                    Some((ufo, _, true)) => ufo_hits.send(UfoHit(ufo)),
+                    Some((enemy, _, false)) => enemy_hits.send(EnemyHit { enemy, damage: 1 }),
+                    None => {}
+                }
+            }
+        }
+        Err(_) => {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.6, 0.9, 1., 0.85),
+                        custom_size: size,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                Beam {
+                    tick: Timer::from_seconds(BEAM_TICK, TimerMode::Repeating),
+                },
+                Name::new("Beam"),
+            ));
+        }
+    }
+
+    player_state.last_fire = time.elapsed_seconds_f64();
+    if settings.overheat {
+        player_state.heat = (player_state.heat + BEAM_HEAT_RATE * time.delta_seconds()).min(1.);
+        player_state.overheated = player_state.heat >= 1.;
+    }
+}
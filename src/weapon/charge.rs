@@ -0,0 +1,101 @@
+use crate::components::{Damage, Player, SpriteSize};
+use crate::weapon::{spawn_projectile, Projectile, WeaponMode};
+use crate::{FireSettings, GameTexture, PlayerState};
+use bevy::prelude::*;
+
+/// seconds of holding Space for a full charge
+const CHARGE_TIME: f32 = 1.2;
+/// damage of a fully charged shot, an uncharged one does 1
+const CHARGE_DAMAGE_MAX: u32 = 6;
+/// sprite (and hitbox) scale of a fully charged shot, relative to a laser
+const CHARGE_SCALE_MAX: f32 = 3.;
+const CHARGE_SPEED: f32 = 1.2;
+
+/// Glow in front of the ship showing the charge building up.
+#[derive(Component)]
+pub struct ChargeOrb;
+
+/// Scale of the shot released at `charge`, from 1 to `CHARGE_SCALE_MAX`.
+fn charge_scale(charge: f32) -> f32 {
+    1. + charge * (CHARGE_SCALE_MAX - 1.)
+}
+
+pub fn player_charge_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    settings: Res<FireSettings>,
+    game_texture: Res<GameTexture>,
+    mut player_state: ResMut<PlayerState>,
+    player_query: Query<(Entity, &Transform, &SpriteSize), With<Player>>,
+    mut orb_query: Query<(Entity, &mut Transform), (With<ChargeOrb>, Without<Player>)>,
+) {
+    let player = player_query.get_single().ok();
+    let charging = player.is_some()
+        && player_state.weapon_mode == WeaponMode::Charge
+        && !player_state.overheated;
+
+    if charging && keyboard_input.pressed(KeyCode::Space) {
+        player_state.charge = (player_state.charge + time.delta_seconds() / CHARGE_TIME).min(1.);
+        // the size of the shot it would release; relative to the parent, which
+        // is scaled by SPRITE_SCALE already, like the shot will be
+        let size = charge_scale(player_state.charge);
+        let orb_scale = Vec3::new(size, size, 1.);
+        match orb_query.get_single_mut() {
+            Ok((_, mut transform)) => transform.scale = orb_scale,
+            Err(_) => {
+                let (player_entity, _, player_size) = player.unwrap();
+                commands.entity(player_entity).with_children(|parent| {
+                    parent.spawn((
+                        SpriteBundle {
+                            texture: game_texture.player_laser.image.clone(),
+                            sprite: Sprite {
+                                color: Color::rgba(1., 1., 1., 0.7),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(0., player_size.0.y / 2. + 20., 1.)
+                                .with_scale(orb_scale),
+                            ..default()
+                        },
+                        ChargeOrb,
+                    ));
+                });
+            }
+        }
+        return;
+    }
+
+    for (orb_entity, _) in orb_query.iter() {
+        commands.entity(orb_entity).despawn_recursive();
+    }
+    if player_state.charge == 0. {
+        return;
+    }
+
+    let charge = player_state.charge;
+    player_state.charge = 0.;
+    let Some((_, player_transform, player_size)) = player.filter(|_| charging) else {
+        return;
+    };
+
+    // release
+    let scale = charge_scale(charge);
+    let damage = 1 + (charge * (CHARGE_DAMAGE_MAX - 1) as f32).round() as u32;
+    let position = player_transform.translation.truncate()
+        + Vec2::new(0., player_size.0.y / 2. * player_transform.scale.y);
+    let projectile = spawn_projectile(
+        &mut commands,
+        &game_texture,
+        Projectile::PlayerLaser,
+        position,
+        Vec2::Y * CHARGE_SPEED,
+        scale,
+    );
+    commands.entity(projectile).insert(Damage(damage));
+
+    player_state.last_fire = time.elapsed_seconds_f64();
+    if settings.overheat {
+        player_state.heat = (player_state.heat + settings.heat_per_shot * scale).min(1.);
+        player_state.overheated = player_state.heat >= 1.;
+    }
+}
@@ -0,0 +1,71 @@
+use crate::components::{Damage, FaceVelocity, Homing, Lifetime, Player, SpriteSize};
+use crate::weapon::{spawn_projectile, Projectile, WeaponMode};
+use crate::{FireSettings, GameTexture, PlayerState};
+use bevy::prelude::*;
+
+/// seconds between two salvos while Space is held
+const MISSILE_INTERVAL: f64 = 0.6;
+const MISSILE_SPEED: f32 = 1.2;
+/// radians per second
+const MISSILE_TURN_RATE: f32 = 3.;
+/// seconds before a missile that found nothing burns out
+const MISSILE_LIFETIME: f32 = 6.;
+const MISSILE_DAMAGE: u32 = 2;
+const MISSILE_SCALE: f32 = 0.7;
+
+pub fn player_missile_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    settings: Res<FireSettings>,
+    game_texture: Res<GameTexture>,
+    mut player_state: ResMut<PlayerState>,
+    player_query: Query<(&Transform, &SpriteSize), With<Player>>,
+) {
+    if player_state.weapon_mode != WeaponMode::Missile || player_state.overheated {
+        return;
+    }
+    let Ok((player_transform, player_size)) = player_query.get_single() else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+    let interval = MISSILE_INTERVAL.max(settings.min_interval);
+    if !keyboard_input.pressed(KeyCode::Space) || now - player_state.last_fire < interval {
+        return;
+    }
+
+    // one missile from each wing, launched outward before they turn
+    let x_offset = player_size.0.x / 2. * player_transform.scale.x - 5.;
+    for side in [-1., 1.] {
+        let position = player_transform.translation.truncate() + Vec2::new(side * x_offset, 0.);
+        let velocity = Vec2::new(side * 0.5, 1.).normalize() * MISSILE_SPEED;
+        let missile = spawn_projectile(
+            &mut commands,
+            &game_texture,
+            Projectile::PlayerLaser,
+            position,
+            velocity,
+            MISSILE_SCALE,
+        );
+        commands.entity(missile).insert((
+            Sprite {
+                color: Color::rgb(1., 0.6, 0.2),
+                ..default()
+            },
+            Damage(MISSILE_DAMAGE),
+            Homing {
+                target: None,
+                turn_rate: MISSILE_TURN_RATE,
+            },
+            FaceVelocity,
+            Lifetime::from_seconds(MISSILE_LIFETIME),
+            Name::new("player_missile"),
+        ));
+    }
+
+    player_state.last_fire = now;
+    if settings.overheat {
+        player_state.heat = (player_state.heat + settings.heat_per_shot * 2.).min(1.);
+        player_state.overheated = player_state.heat >= 1.;
+    }
+}
@@ -0,0 +1,443 @@
+use crate::components::{
+    Destructible, FromEnemy, FromPlayer, Hitbox, Laser, Movable, Player, SpriteSize, Velocity,
+};
+use crate::ship::Loadout;
+use crate::{AppState, GameSprite, GameTexture, PlayerState, SPRITE_SCALE};
+use bevy::prelude::*;
+use rand::{thread_rng, Rng};
+use std::f32::consts::PI;
+
+pub mod beam;
+pub mod charge;
+pub mod missile;
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                weapon_fire_system,
+                weapon_mode_system,
+                charge::player_charge_system,
+                beam::player_beam_system,
+                missile::player_missile_system,
+            )
+                .in_set(OnUpdate(AppState::InGame)),
+        );
+    }
+}
+
+/// The gun the player currently fires with Space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum WeaponMode {
+    #[default]
+    Laser,
+    /// grows while Space is held, fires on release
+    Charge,
+    /// continuous ray hitting the first enemy above the ship
+    Beam,
+    /// slow salvos of missiles homing on the nearest enemy
+    Missile,
+}
+
+fn weapon_mode_system(keyboard_input: Res<Input<KeyCode>>, mut player_state: ResMut<PlayerState>) {
+    for (key, mode) in [
+        (KeyCode::Key1, WeaponMode::Laser),
+        (KeyCode::Key2, WeaponMode::Charge),
+        (KeyCode::Key3, WeaponMode::Beam),
+        (KeyCode::Key4, WeaponMode::Missile),
+    ] {
+        if keyboard_input.just_pressed(key) {
+            player_state.weapon_mode = mode;
+            player_state.charge = 0.;
+        }
+    }
+}
+
+/// What a weapon shoots. Decides the sprite, hitbox and team of the projectile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Projectile {
+    PlayerLaser,
+    EnemyLaser,
+}
+
+impl Projectile {
+    fn sprite<'a>(&self, game_texture: &'a GameTexture) -> &'a GameSprite {
+        match self {
+            Projectile::PlayerLaser => &game_texture.player_laser,
+            Projectile::EnemyLaser => &game_texture.enemy_laser,
+        }
+    }
+
+    /// whether the other team can shoot it down
+    fn destructible(&self) -> bool {
+        match self {
+            Projectile::PlayerLaser => false,
+            Projectile::EnemyLaser => true,
+        }
+    }
+}
+
+/// Where the center of a volley points.
+#[derive(Clone, Debug)]
+pub enum Aim {
+    Fixed(Vec2),
+    /// toward the player, optionally leading its movement
+    AtPlayer {
+        lead: bool,
+    },
+    /// rotates by `step` radians after every volley
+    Spiral {
+        step: f32,
+        angle: f32,
+    },
+}
+
+/// A gun described as data. Whoever owns it pulls `trigger`; `weapon_fire_system`
+/// does the rest for both teams.
+#[derive(Component, Clone, Debug)]
+pub struct Weapon {
+    /// spawn points relative to the owner, in world units
+    pub muzzles: Vec<Vec2>,
+    pub projectile: Projectile,
+    pub speed: f32,
+    /// minimum time between two volleys, also the gap between volleys of a burst
+    pub cooldown: Timer,
+    /// projectiles per muzzle and per volley
+    pub ways: u32,
+    /// arc in radians covered by the `ways` projectiles
+    pub spread: f32,
+    pub aim: Aim,
+    /// volleys fired per trigger pull
+    pub burst: u32,
+    pub trigger: bool,
+    burst_left: u32,
+}
+
+impl Weapon {
+    pub fn new(projectile: Projectile, aim: Aim, speed: f32, cooldown: f32) -> Self {
+        Self {
+            muzzles: vec![Vec2::ZERO],
+            projectile,
+            speed,
+            cooldown: Timer::from_seconds(cooldown, TimerMode::Once),
+            ways: 1,
+            spread: 0.,
+            aim,
+            burst: 1,
+            trigger: false,
+            burst_left: 0,
+        }
+    }
+
+    pub fn with_muzzles(mut self, muzzles: Vec<Vec2>) -> Self {
+        self.muzzles = muzzles;
+        self
+    }
+
+    pub fn with_spread(mut self, ways: u32, spread: f32) -> Self {
+        self.ways = ways;
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    /// Projectiles a trigger pull fires, over every volley of the burst.
+    pub fn shots_per_trigger(&self) -> usize {
+        self.muzzles.len() * self.ways as usize * self.burst as usize
+    }
+
+    /// Projectiles of the current burst that are still to be fired.
+    pub fn shots_queued(&self) -> usize {
+        self.muzzles.len() * self.ways as usize * self.burst_left as usize
+    }
+
+    /// The guns of the player ship.
+    pub fn player(loadout: &Loadout) -> Self {
+        let muzzles = loadout
+            .muzzles
+            .iter()
+            .map(|(x, y)| Vec2::new(x * SPRITE_SCALE.0, y * SPRITE_SCALE.1))
+            .collect();
+        Weapon::new(Projectile::PlayerLaser, Aim::Fixed(Vec2::Y), 1., 0.)
+            .with_muzzles(muzzles)
+            .with_spread(loadout.ways, loadout.spread)
+    }
+
+    /// A random gun for a regular enemy.
+    pub fn enemy_random(speed: f32) -> Self {
+        let down = Aim::Fixed(Vec2::NEG_Y);
+        let weapon = match thread_rng().gen_range(0..7) {
+            0 => Weapon::new(Projectile::EnemyLaser, down, speed, 0.),
+            1 => Weapon::new(
+                Projectile::EnemyLaser,
+                Aim::AtPlayer { lead: false },
+                speed,
+                0.,
+            ),
+            2 => Weapon::new(
+                Projectile::EnemyLaser,
+                Aim::AtPlayer { lead: true },
+                speed,
+                0.,
+            ),
+            3 => Weapon::new(Projectile::EnemyLaser, down, speed, 0.).with_spread(3, PI / 6.),
+            4 => Weapon::new(Projectile::EnemyLaser, down, speed, 0.).with_spread(5, PI / 3.),
+            5 => Weapon::enemy_spiral(speed, 4, PI / 9.),
+            _ => Weapon::new(
+                Projectile::EnemyLaser,
+                Aim::AtPlayer { lead: false },
+                speed,
+                0.12,
+            )
+            .with_burst(4),
+        };
+        weapon.with_muzzles(vec![Vec2::new(0., -15.)])
+    }
+
+    /// `ways` lasers evenly around the owner, turning by `step` every volley.
+    pub fn enemy_spiral(speed: f32, ways: u32, step: f32) -> Self {
+        Weapon::new(
+            Projectile::EnemyLaser,
+            Aim::Spiral { step, angle: 0. },
+            speed,
+            0.,
+        )
+        .with_spread(ways, 2. * PI * (ways - 1) as f32 / ways as f32)
+    }
+
+    /// Directions of one volley (per muzzle) toward `target`, the player position
+    /// and velocity if there is one.
+    fn volley(&mut self, origin: Vec2, target: Option<(Vec2, Vec2)>) -> Vec<Vec2> {
+        let center = match &mut self.aim {
+            Aim::Fixed(direction) => *direction,
+            Aim::AtPlayer { lead } => aim(origin, target, self.speed, *lead),
+            Aim::Spiral { step, angle } => {
+                let direction = Vec2::from_angle(*angle);
+                *angle = (*angle + *step) % (2. * PI);
+                direction
+            }
+        };
+        fan(center, self.ways, self.spread)
+    }
+}
+
+fn weapon_fire_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_texture: Res<GameTexture>,
+    player_query: Query<(&Transform, &Velocity), With<Player>>,
+    mut weapon_query: Query<(&GlobalTransform, &mut Weapon)>,
+) {
+    let target = player_query.get_single().ok().map(|(transform, velocity)| {
+        (
+            transform.translation.truncate(),
+            Vec2::new(velocity.x, velocity.y),
+        )
+    });
+
+    for (transform, mut weapon) in weapon_query.iter_mut() {
+        weapon.cooldown.tick(time.delta());
+        if weapon.trigger && weapon.burst_left == 0 {
+            weapon.burst_left = weapon.burst;
+        }
+        weapon.trigger = false;
+        if weapon.burst_left == 0 || !weapon.cooldown.finished() {
+            continue;
+        }
+
+        let origin = transform.translation().truncate();
+        let directions = weapon.volley(origin, target);
+        for muzzle in weapon.muzzles.iter() {
+            for direction in directions.iter() {
+                spawn_projectile(
+                    &mut commands,
+                    &game_texture,
+                    weapon.projectile,
+                    origin + *muzzle,
+                    *direction * weapon.speed,
+                    1.,
+                );
+            }
+        }
+        weapon.burst_left -= 1;
+        weapon.cooldown.reset();
+    }
+}
+
+/// Spawn a projectile, `scale` multiplies the regular sprite scale (and hitbox).
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    game_texture: &GameTexture,
+    projectile: Projectile,
+    position: Vec2,
+    velocity: Vec2,
+    scale: f32,
+) -> Entity {
+    let mut entity = commands.spawn((
+        SpriteBundle {
+            texture: projectile.sprite(game_texture).image.clone(),
+            transform: Transform {
+                translation: position.extend(0.),
+                // the laser sprites point up, turn them to face their direction
+                rotation: Quat::from_rotation_z(velocity.y.atan2(velocity.x) - PI / 2.),
+                scale: Vec3::new(SPRITE_SCALE.0 * scale, SPRITE_SCALE.1 * scale, 1.),
+            },
+            ..default()
+        },
+        Laser,
+        SpriteSize(projectile.sprite(game_texture).size),
+        Hitbox(projectile.sprite(game_texture).hitbox),
+        Movable { auto_despawn: true },
+        Velocity {
+            x: velocity.x,
+            y: velocity.y,
+        },
+    ));
+    match projectile {
+        Projectile::PlayerLaser => entity.insert((FromPlayer, Name::new("player_laser"))),
+        Projectile::EnemyLaser => entity.insert((FromEnemy, Name::new("Laser"))),
+    };
+    if projectile.destructible() {
+        entity.insert(Destructible);
+    }
+    entity.id()
+}
+
+/// Direction from `origin` toward the target. With `lead`, aim at the point where
+/// a projectile of `speed` would intercept the target if it keeps its velocity.
+fn aim(origin: Vec2, target: Option<(Vec2, Vec2)>, speed: f32, lead: bool) -> Vec2 {
+    let Some((position, velocity)) = target else {
+        return Vec2::NEG_Y;
+    };
+    let aim_point = if lead {
+        position + velocity * intercept_time(position - origin, velocity, speed).unwrap_or(0.)
+    } else {
+        position
+    };
+    (aim_point - origin).try_normalize().unwrap_or(Vec2::NEG_Y)
+}
+
+/// Smallest positive `t` where |offset + velocity * t| == speed * t.
+fn intercept_time(offset: Vec2, velocity: Vec2, speed: f32) -> Option<f32> {
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2. * offset.dot(velocity);
+    let c = offset.length_squared();
+
+    if a.abs() < f32::EPSILON {
+        return (b.abs() > f32::EPSILON).then(|| -c / b).filter(|t| *t > 0.);
+    }
+
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (t1, t2) = ((-b - root) / (2. * a), (-b + root) / (2. * a));
+    [t1.min(t2), t1.max(t2)].into_iter().find(|t| *t > 0.)
+}
+
+/// `ways` directions evenly spread over `arc`, centered on `center`.
+fn fan(center: Vec2, ways: u32, arc: f32) -> Vec<Vec2> {
+    if ways <= 1 {
+        return vec![center];
+    }
+    let start = center.y.atan2(center.x) - arc / 2.;
+    let step = arc / (ways - 1) as f32;
+    (0..ways)
+        .map(|i| Vec2::from_angle(start + i as f32 * step))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, 1e-5), "{a} != {b}");
+    }
+
+    #[test]
+    fn intercept_still_target() {
+        assert_eq!(intercept_time(Vec2::new(0., 10.), Vec2::ZERO, 5.), Some(2.));
+    }
+
+    #[test]
+    fn intercept_crossing_target() {
+        let (offset, velocity, speed) = (Vec2::new(0., 10.), Vec2::new(5., 0.), 10.);
+        let t = intercept_time(offset, velocity, speed).unwrap();
+        // the shot and the target meet
+        assert!(((offset + velocity * t).length() - speed * t).abs() < 1e-4);
+    }
+
+    #[test]
+    fn intercept_target_faster_than_the_shot() {
+        // running away, it can't be caught
+        assert_eq!(
+            intercept_time(Vec2::new(0., 10.), Vec2::new(0., 10.), 5.),
+            None
+        );
+        // coming closer, the first meeting is the one
+        let t = intercept_time(Vec2::new(0., 10.), Vec2::new(0., -20.), 5.).unwrap();
+        assert!((t - 0.4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn intercept_target_as_fast_as_the_shot() {
+        // the quadratic term vanishes
+        assert_eq!(
+            intercept_time(Vec2::new(0., 10.), Vec2::new(0., -5.), 5.),
+            Some(1.)
+        );
+        assert_eq!(
+            intercept_time(Vec2::new(0., 10.), Vec2::new(0., 5.), 5.),
+            None
+        );
+        // and so does the linear one
+        assert_eq!(
+            intercept_time(Vec2::new(0., 10.), Vec2::new(5., 0.), 5.),
+            None
+        );
+        assert_eq!(intercept_time(Vec2::new(0., 10.), Vec2::ZERO, 0.), None);
+    }
+
+    #[test]
+    fn fan_single_way() {
+        let center = Vec2::new(0.6, -0.8);
+        assert_eq!(fan(center, 1, PI), vec![center]);
+        assert_eq!(fan(center, 0, PI), vec![center]);
+    }
+
+    #[test]
+    fn fan_without_arc() {
+        let directions = fan(Vec2::NEG_Y, 3, 0.);
+        assert_eq!(directions.len(), 3);
+        for direction in directions {
+            assert_near(direction, Vec2::NEG_Y);
+        }
+    }
+
+    #[test]
+    fn fan_spreads_evenly_around_the_center() {
+        let directions = fan(Vec2::Y, 3, PI / 2.);
+        let diagonal = 0.5_f32.sqrt();
+        assert_near(directions[0], Vec2::new(diagonal, diagonal));
+        assert_near(directions[1], Vec2::Y);
+        assert_near(directions[2], Vec2::new(-diagonal, diagonal));
+    }
+
+    #[test]
+    fn fan_full_circle() {
+        // the spiral pattern: 4 ways a quarter turn apart
+        let directions = fan(Vec2::X, 4, 2. * PI * 3. / 4.);
+        let expected = [-0.75 * PI, -0.25 * PI, 0.25 * PI, 0.75 * PI];
+        for (direction, angle) in directions.into_iter().zip(expected) {
+            assert_near(direction, Vec2::from_angle(angle));
+        }
+    }
+}